@@ -1,35 +1,59 @@
-use icu::locid::{langid, LanguageIdentifier};
-use icu_datagen::{CldrLocaleSubset, Out, SourceData};
-use std::{fs::File, path::Path};
+use std::{env, fs, path::Path};
 
-fn new_database() {
-  let exists = Path::new("db.db").try_exists();
-  if !matches!(exists, Ok(true)) {
-    let _ = std::fs::copy("schema.db", "db.db");
+/// The ICU data is checked in, so building never needs network access. It's regenerated on demand
+/// by the `datagen` binary.
+fn check_icu_data() {
+  // Building `datagen` itself is how the file gets created in the first place.
+  if env::var_os("CARGO_FEATURE_DATAGEN").is_some() {
+    return;
+  }
+
+  if !Path::new("i18n/data.postcard").exists() {
+    panic!(
+      "i18n/data.postcard is missing, generate it with `cargo run --bin datagen --features datagen`"
+    );
   }
 }
 
-const DISCORD_LOCALES: &[LanguageIdentifier] = &[langid!("en-GB"), langid!("en"), langid!("pl")];
+/// Lists the translation files in `i18n/` for `util::i18n`, which embeds them, so adding a language
+/// only takes adding its file.
+fn write_locales() {
+  let mut locales = fs::read_dir("i18n")
+    .expect("i18n/ should be readable")
+    .map(|entry| entry.expect("i18n/ should be readable").path())
+    .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+    .map(|path| {
+      let locale = path.file_stem().unwrap().to_str().unwrap().to_string();
+      let path = fs::canonicalize(&path).unwrap();
+      (locale, path)
+    })
+    .collect::<Vec<_>>();
+  locales.sort();
 
-fn icu_gen_list() {
-  icu_datagen::datagen(
-    Some(DISCORD_LOCALES),
-    &icu_datagen::keys(&[
-      "list/and@1",
-      "fallback/likelysubtags@1",
-      "fallback/parents@1",
-    ]),
-    &SourceData::default()
-      .with_cldr_for_tag("41.0.0", CldrLocaleSubset::Modern)
-      .unwrap(),
-    vec![Out::Blob(Box::new(
-      File::create("i18n/data.postcard").unwrap(),
-    ))],
+  let names = locales
+    .iter()
+    .map(|(locale, _)| format!("{:?}", locale))
+    .collect::<Vec<_>>();
+  let files = locales
+    .iter()
+    .map(|(locale, path)| format!("({:?}, include_str!({:?}))", locale, path))
+    .collect::<Vec<_>>();
+  let out = Path::new(&env::var("OUT_DIR").unwrap()).join("locales.rs");
+  fs::write(
+    out,
+    format!(
+      "pub const LOCALES: &[&str] = &[{}];\nconst TRANSLATIONS: &[(&str, &str)] = &[{}];\n",
+      names.join(", "),
+      files.join(", ")
+    ),
   )
   .unwrap();
 }
 
 fn main() {
-  new_database();
-  icu_gen_list();
+  // `sqlx::migrate!` embeds the migrations, so new ones have to trigger a rebuild.
+  println!("cargo:rerun-if-changed=migrations");
+  println!("cargo:rerun-if-changed=i18n");
+  check_icu_data();
+  write_locales();
 }
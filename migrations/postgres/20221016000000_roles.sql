@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS "roles" (
+  "guild_id" TEXT NOT NULL,
+  "role_id" TEXT NOT NULL UNIQUE,
+  "label" TEXT NOT NULL,
+  "description" TEXT,
+  PRIMARY KEY ("role_id")
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "both" ON "roles" ("role_id", "guild_id");
+
+CREATE INDEX IF NOT EXISTS "guild" ON "roles" ("guild_id");
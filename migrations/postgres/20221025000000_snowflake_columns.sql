@@ -0,0 +1,14 @@
+-- Store snowflakes as integers, and drop the "both" index and the UNIQUE constraint on role_id,
+-- which the primary key already covers.
+DROP INDEX IF EXISTS "both";
+ALTER TABLE "roles" DROP CONSTRAINT IF EXISTS "roles_role_id_key";
+ALTER TABLE "roles"
+  ALTER COLUMN "guild_id" TYPE BIGINT USING "guild_id"::BIGINT,
+  ALTER COLUMN "role_id" TYPE BIGINT USING "role_id"::BIGINT;
+
+ALTER TABLE "role_labels"
+  ALTER COLUMN "role_id" TYPE BIGINT USING "role_id"::BIGINT;
+
+ALTER TABLE "guilds"
+  ALTER COLUMN "guild_id" TYPE BIGINT USING "guild_id"::BIGINT,
+  ALTER COLUMN "log_channel" TYPE BIGINT USING "log_channel"::BIGINT;
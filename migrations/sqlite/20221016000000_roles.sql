@@ -0,0 +1,12 @@
+-- Databases copied from the old `schema.db` already have this table, so this is a no-op for them.
+CREATE TABLE IF NOT EXISTS "roles" (
+  "guild_id" TEXT NOT NULL,
+  "role_id" TEXT NOT NULL UNIQUE,
+  "label" TEXT NOT NULL,
+  "description" TEXT,
+  PRIMARY KEY ("role_id")
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "both" ON "roles" ("role_id", "guild_id");
+
+CREATE INDEX IF NOT EXISTS "guild" ON "roles" ("guild_id");
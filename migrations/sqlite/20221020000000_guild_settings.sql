@@ -0,0 +1,16 @@
+-- Log channel and language picked with `/config log` and `/config language`.
+CREATE TABLE IF NOT EXISTS "guilds" (
+  "guild_id" TEXT NOT NULL,
+  "log_channel" TEXT,
+  "locale" TEXT,
+  PRIMARY KEY ("guild_id")
+);
+
+-- Selfrole labels for other languages, set with `/config translate`.
+CREATE TABLE IF NOT EXISTS "role_labels" (
+  "role_id" TEXT NOT NULL,
+  "locale" TEXT NOT NULL,
+  "label" TEXT NOT NULL,
+  "description" TEXT,
+  PRIMARY KEY ("role_id", "locale")
+);
@@ -0,0 +1,38 @@
+-- Store snowflakes as integers, and drop the "both" index, which the primary key already covers.
+-- SQLite can't change column types in place, so each table is rebuilt.
+CREATE TABLE "roles_new" (
+  "guild_id" INTEGER NOT NULL,
+  "role_id" INTEGER NOT NULL PRIMARY KEY,
+  "label" TEXT NOT NULL,
+  "description" TEXT
+);
+INSERT INTO "roles_new"
+  SELECT CAST("guild_id" AS INTEGER), CAST("role_id" AS INTEGER), "label", "description"
+  FROM "roles";
+DROP TABLE "roles";
+ALTER TABLE "roles_new" RENAME TO "roles";
+CREATE INDEX "guild" ON "roles" ("guild_id");
+
+CREATE TABLE "role_labels_new" (
+  "role_id" INTEGER NOT NULL,
+  "locale" TEXT NOT NULL,
+  "label" TEXT NOT NULL,
+  "description" TEXT,
+  PRIMARY KEY ("role_id", "locale")
+);
+INSERT INTO "role_labels_new"
+  SELECT CAST("role_id" AS INTEGER), "locale", "label", "description"
+  FROM "role_labels";
+DROP TABLE "role_labels";
+ALTER TABLE "role_labels_new" RENAME TO "role_labels";
+
+CREATE TABLE "guilds_new" (
+  "guild_id" INTEGER NOT NULL PRIMARY KEY,
+  "log_channel" INTEGER,
+  "locale" TEXT
+);
+INSERT INTO "guilds_new"
+  SELECT CAST("guild_id" AS INTEGER), CAST("log_channel" AS INTEGER), "locale"
+  FROM "guilds";
+DROP TABLE "guilds";
+ALTER TABLE "guilds_new" RENAME TO "guilds";
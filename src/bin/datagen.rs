@@ -0,0 +1,65 @@
+//! Regenerates `i18n/data.postcard`, the ICU data the bot formats messages with. It downloads CLDR,
+//! so it's not part of the regular build; run it with `cargo run --bin datagen --features datagen`
+//! after changing the locales or keys below, and commit the new file.
+
+use icu_datagen::{CldrLocaleSubset, Out, SourceData};
+use icu_locid::{langid, LanguageIdentifier};
+use std::fs::File;
+
+const CLDR_TAG: &str = "41.0.0";
+const OUTPUT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/i18n/data.postcard");
+
+/// Every locale Discord users can pick, so formatting works in each of them even before there's a
+/// translation file for it.
+const DISCORD_LOCALES: &[LanguageIdentifier] = &[
+  langid!("bg"),
+  langid!("cs"),
+  langid!("da"),
+  langid!("de"),
+  langid!("el"),
+  langid!("en"),
+  langid!("en-GB"),
+  langid!("en-US"),
+  langid!("es-ES"),
+  langid!("fi"),
+  langid!("fr"),
+  langid!("hi"),
+  langid!("hr"),
+  langid!("hu"),
+  langid!("id"),
+  langid!("it"),
+  langid!("ja"),
+  langid!("ko"),
+  langid!("lt"),
+  langid!("nl"),
+  langid!("no"),
+  langid!("pl"),
+  langid!("pt-BR"),
+  langid!("ro"),
+  langid!("ru"),
+  langid!("sv-SE"),
+  langid!("th"),
+  langid!("tr"),
+  langid!("uk"),
+  langid!("vi"),
+  langid!("zh-CN"),
+  langid!("zh-TW"),
+];
+
+fn main() -> anyhow::Result<()> {
+  icu_datagen::datagen(
+    Some(DISCORD_LOCALES),
+    &icu_datagen::keys(&[
+      "list/and@1",
+      "list/or@1",
+      "plurals/cardinal@1",
+      "fallback/likelysubtags@1",
+      "fallback/parents@1",
+    ]),
+    &SourceData::default().with_cldr_for_tag(CLDR_TAG, CldrLocaleSubset::Modern)?,
+    vec![Out::Blob(Box::new(File::create(OUTPUT)?))],
+  )?;
+
+  println!("Wrote {}", OUTPUT);
+  Ok(())
+}
@@ -1,174 +1,250 @@
-use json_gettext::{get_text, static_json_gettext_build, JSONGetText};
-use std::env;
+use anyhow::Context;
+use serde_json::Value;
+use std::{
+  cell::RefCell,
+  collections::{BTreeMap, BTreeSet, HashMap},
+  env, fs,
+  path::{Path, PathBuf},
+};
 use twilight_http::client::ClientBuilder;
 use twilight_model::{
   application::command::{
-    BaseCommandOptionData, ChoiceCommandOptionData, CommandOption, CommandType,
-    OptionsCommandOptionData,
+    BaseCommandOptionData, ChannelCommandOptionData, ChoiceCommandOptionData, Command,
+    CommandOption, CommandOptionChoice, CommandType, OptionsCommandOptionData,
   },
+  channel::ChannelType,
   guild::Permissions,
+  id::{marker::GuildMarker, Id},
 };
 use twilight_util::builder::command::CommandBuilder;
 
-fn gdv(ctx: &JSONGetText, key: &str) -> String {
-  get_text!(ctx, key).unwrap().to_string()
+// Component custom ids are only used by the bot itself.
+#[allow(dead_code)]
+#[path = "../commands/manifest.rs"]
+mod manifest;
+
+const DEFAULT_LOCALE: &str = "en-US";
+
+/// Locales Discord accepts in command name and description localizations.
+const DISCORD_LOCALES: &[&str] = &[
+  "bg", "cs", "da", "de", "el", "en-GB", "en-US", "es-ES", "fi", "fr", "hi", "hr", "hu", "id",
+  "it", "ja", "ko", "lt", "nl", "no", "pl", "pt-BR", "ro", "ru", "sv-SE", "th", "tr", "uk", "vi",
+  "zh-CN", "zh-TW",
+];
+
+/// Key without its plural category, since languages don't all use the same categories.
+fn base_key(key: &str) -> &str {
+  key.split('#').next().unwrap_or(key)
 }
 
-fn gtv<'a>(ctx: &'a JSONGetText<'a>, locale: &'a str, key: &'a str) -> (String, String) {
-  (
-    locale.to_string(),
-    get_text!(ctx, locale, key).unwrap().to_string(),
-  )
+/// Every translation file in `i18n/`, keyed by the locale in its file name.
+struct Translations {
+  locales: BTreeMap<String, HashMap<String, String>>,
+  /// Every key looked up so far, so the tests can check them against each translation file.
+  used: RefCell<BTreeSet<String>>,
+}
+
+impl Translations {
+  /// Load all translation files, making sure each one has exactly the keys of the default locale.
+  fn load(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+    let mut locales = BTreeMap::new();
+
+    for entry in fs::read_dir(dir.as_ref())? {
+      let path = entry?.path();
+      if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+        continue;
+      }
+
+      let locale = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .with_context(|| format!("Invalid translation file name {}", path.display()))?
+        .to_string();
+      let texts: HashMap<String, String> = serde_json::from_str(&fs::read_to_string(&path)?)
+        .with_context(|| format!("Invalid translation file {}", path.display()))?;
+
+      locales.insert(locale, texts);
+    }
+
+    let default_texts = locales
+      .get(DEFAULT_LOCALE)
+      .with_context(|| format!("Missing translation file for {}", DEFAULT_LOCALE))?;
+    let default_keys = default_texts
+      .keys()
+      .map(|key| base_key(key))
+      .collect::<BTreeSet<_>>();
+
+    let mut problems = Vec::new();
+    for (locale, texts) in &locales {
+      let keys = texts
+        .keys()
+        .map(|key| base_key(key))
+        .collect::<BTreeSet<_>>();
+      for key in default_keys.difference(&keys) {
+        problems.push(format!("{}: missing key {}", locale, key));
+      }
+      // The bot refuses to load a file with any key the default one doesn't have, including
+      // plural categories the default language doesn't use.
+      for key in texts.keys().filter(|key| !default_texts.contains_key(*key)) {
+        problems.push(format!("{}: extra key {}", locale, key));
+      }
+      for key in texts.keys().filter(|key| *key != base_key(key)) {
+        let other = format!("{}#other", base_key(key));
+        if !texts.contains_key(&other) {
+          problems.push(format!("{}: missing key {}", locale, other));
+        }
+      }
+    }
+    problems.sort();
+    problems.dedup();
+    anyhow::ensure!(
+      problems.is_empty(),
+      "Translation files don't match {}.json:\n{}",
+      DEFAULT_LOCALE,
+      problems.join("\n")
+    );
+
+    for locale in locales.keys() {
+      if !DISCORD_LOCALES.contains(&locale.as_str()) {
+        eprintln!(
+          "{} is not a Discord locale, its command localizations will be skipped",
+          locale
+        );
+      }
+    }
+
+    Ok(Self {
+      locales,
+      used: RefCell::default(),
+    })
+  }
+
+  fn get_in(&self, locale: &str, key: &str) -> String {
+    self.used.borrow_mut().insert(key.to_string());
+    match self.locales[locale].get(key) {
+      Some(text) => text.clone(),
+      None => {
+        eprintln!("Missing translation for {} in {}.json", key, locale);
+        key.to_string()
+      }
+    }
+  }
+
+  fn get(&self, key: &str) -> String {
+    self.get_in(DEFAULT_LOCALE, key)
+  }
+
+  /// Default name of a command or option, which has to match the name the bot dispatches on.
+  fn name(&self, key: &str, name: &str) -> anyhow::Result<String> {
+    let text = self.get(key);
+    anyhow::ensure!(
+      text == name,
+      "{} is \"{}\" in {}.json, but the bot expects \"{}\"",
+      key,
+      text,
+      DEFAULT_LOCALE,
+      name
+    );
+
+    Ok(text)
+  }
+
+  /// Translations of `key` for every Discord locale other than the default one.
+  fn localizations(&self, key: &str) -> HashMap<String, String> {
+    self
+      .locales
+      .keys()
+      .filter(|locale| *locale != DEFAULT_LOCALE && DISCORD_LOCALES.contains(&locale.as_str()))
+      .map(|locale| (locale.clone(), self.get_in(locale, key)))
+      .collect()
+  }
+}
+
+const USAGE: &str = "\
+Usage: deploy [--guild <id>] [--diff] [--dry-run [--out <file>]]
+
+  --guild <id>  Register the commands in a single guild instead of globally
+  --diff        Print what changes compared to the registered commands before applying
+  --dry-run     Print the commands as JSON without registering them, no token needed
+  --out <file>  Write the dry run JSON to a file instead of stdout";
+
+/// Fields Discord fills in itself, which are never part of a local command definition.
+const GENERATED_FIELDS: &[&str] = &["application_id", "guild_id", "id", "version"];
+
+#[derive(Debug, Default)]
+struct Args {
+  guild_id: Option<Id<GuildMarker>>,
+  diff: bool,
+  dry_run: bool,
+  out: Option<PathBuf>,
+}
+
+impl Args {
+  fn parse() -> anyhow::Result<Self> {
+    let mut parsed = Self::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "--guild" => {
+          let guild_id = args.next().context("--guild needs a guild id")?;
+          parsed.guild_id = Some(
+            guild_id
+              .parse()
+              .with_context(|| format!("Invalid guild id {}", guild_id))?,
+          );
+        }
+        "--diff" => parsed.diff = true,
+        "--dry-run" => parsed.dry_run = true,
+        "--out" => parsed.out = Some(args.next().context("--out needs a file path")?.into()),
+        "-h" | "--help" => {
+          println!("{}", USAGE);
+          std::process::exit(0);
+        }
+        _ => anyhow::bail!("Unknown argument {}\n\n{}", arg, USAGE),
+      }
+    }
+
+    anyhow::ensure!(
+      parsed.out.is_none() || parsed.dry_run,
+      "--out only works together with --dry-run"
+    );
+    anyhow::ensure!(
+      !(parsed.diff && parsed.dry_run),
+      "--diff needs the registered commands, so it can't be used with --dry-run"
+    );
+
+    Ok(parsed)
+  }
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-  dotenvy::dotenv()?;
-  let ctx = static_json_gettext_build!(
-    "en-US";
-    "en-US" => "i18n/en-US.json",
-    "pl" => "i18n/pl.json"
-  )
-  .unwrap();
+  let args = Args::parse()?;
+  // A dry run doesn't need a token, so there may be no `.env` at all.
+  dotenvy::dotenv().ok();
+  let tr = Translations::load("i18n")?;
 
-  let roles_command = CommandBuilder::new(
-    gdv(&ctx, "cmd::roles:name"),
-    gdv(&ctx, "cmd::roles:desc"),
-    CommandType::ChatInput,
-  )
-  .dm_permission(false)
-  .name_localizations(vec![gtv(&ctx, "pl", "cmd::roles:name")])
-  .description_localizations(vec![gtv(&ctx, "pl", "cmd::roles:desc")])
-  .build();
-
-  let persist_command = CommandBuilder::new(
-    gdv(&ctx, "cmd::persist:name"),
-    gdv(&ctx, "cmd::persist:desc"),
-    CommandType::ChatInput,
-  )
-  .dm_permission(false)
-  .default_member_permissions(Permissions::MANAGE_ROLES)
-  .option(CommandOption::String(ChoiceCommandOptionData {
-    name: gdv(&ctx, "cmd::persist::content:name"),
-    description: gdv(&ctx, "cmd::persist::content:desc"),
-    name_localizations: Some(
-      vec![gtv(&ctx, "pl", "cmd::persist::content:name")]
-        .into_iter()
-        .collect(),
-    ),
-    description_localizations: Some(
-      vec![gtv(&ctx, "pl", "cmd::persist::content:desc")]
-        .into_iter()
-        .collect(),
-    ),
-    ..Default::default()
-  }))
-  .name_localizations(vec![gtv(&ctx, "pl", "cmd::persist:name")])
-  .description_localizations(vec![gtv(&ctx, "pl", "cmd::persist:desc")])
-  .build();
-
-  let config_command = CommandBuilder::new(
-    gdv(&ctx, "cmd::config:name"),
-    gdv(&ctx, "cmd::config:desc"),
-    CommandType::ChatInput,
-  )
-  .dm_permission(false)
-  .default_member_permissions(Permissions::MANAGE_ROLES)
-  .option(CommandOption::SubCommand(OptionsCommandOptionData {
-    name: gdv(&ctx, "cmd::config::add:name"),
-    description: gdv(&ctx, "cmd::config::add:desc"),
-    options: vec![
-      CommandOption::Role(BaseCommandOptionData {
-        name: gdv(&ctx, "cmd::config::add::role:name"),
-        description: gdv(&ctx, "cmd::config::add::role:desc"),
-        required: true,
-        name_localizations: Some(
-          vec![gtv(&ctx, "pl", "cmd::config::add::role:name")]
-            .into_iter()
-            .collect(),
-        ),
-        description_localizations: Some(
-          vec![gtv(&ctx, "pl", "cmd::config::add::role:desc")]
-            .into_iter()
-            .collect(),
-        ),
-      }),
-      CommandOption::String(ChoiceCommandOptionData {
-        name: gdv(&ctx, "cmd::config::add::label:name"),
-        description: gdv(&ctx, "cmd::config::add::label:desc"),
-        name_localizations: Some(
-          vec![gtv(&ctx, "pl", "cmd::config::add::label:name")]
-            .into_iter()
-            .collect(),
-        ),
-        description_localizations: Some(
-          vec![gtv(&ctx, "pl", "cmd::config::add::label:desc")]
-            .into_iter()
-            .collect(),
-        ),
-        ..Default::default()
-      }),
-      CommandOption::String(ChoiceCommandOptionData {
-        name: gdv(&ctx, "cmd::config::add::description:name"),
-        description: gdv(&ctx, "cmd::config::add::description:desc"),
-        name_localizations: Some(
-          vec![gtv(&ctx, "pl", "cmd::config::add::description:name")]
-            .into_iter()
-            .collect(),
-        ),
-        description_localizations: Some(
-          vec![gtv(&ctx, "pl", "cmd::config::add::description:desc")]
-            .into_iter()
-            .collect(),
-        ),
-        ..Default::default()
-      }),
-    ],
-    name_localizations: Some(
-      vec![gtv(&ctx, "pl", "cmd::config::add:name")]
-        .into_iter()
-        .collect(),
-    ),
-    description_localizations: Some(
-      vec![gtv(&ctx, "pl", "cmd::config::add:desc")]
-        .into_iter()
-        .collect(),
-    ),
-  }))
-  .option(CommandOption::SubCommand(OptionsCommandOptionData {
-    name: gdv(&ctx, "cmd::config::remove:name"),
-    description: gdv(&ctx, "cmd::config::remove:desc"),
-    options: vec![CommandOption::Role(BaseCommandOptionData {
-      name: gdv(&ctx, "cmd::config::remove::role:name"),
-      description: gdv(&ctx, "cmd::config::remove::role:desc"),
-      required: true,
-      name_localizations: Some(
-        vec![gtv(&ctx, "pl", "cmd::config::remove::role:name")]
-          .into_iter()
-          .collect(),
-      ),
-      description_localizations: Some(
-        vec![gtv(&ctx, "pl", "cmd::config::remove::role:desc")]
-          .into_iter()
-          .collect(),
-      ),
-    })],
-    name_localizations: Some(
-      vec![gtv(&ctx, "pl", "cmd::config::remove:name")]
-        .into_iter()
-        .collect(),
-    ),
-    description_localizations: Some(
-      vec![gtv(&ctx, "pl", "cmd::config::remove:desc")]
-        .into_iter()
-        .collect(),
-    ),
-  }))
-  .name_localizations(vec![gtv(&ctx, "pl", "cmd::config:name")])
-  .description_localizations(vec![gtv(&ctx, "pl", "cmd::config:desc")])
-  .build();
-
-  let client = ClientBuilder::new().token(env::var("TOKEN")?).build();
+  let commands = [
+    roles_command(&tr)?,
+    persist_command(&tr)?,
+    config_command(&tr)?,
+  ];
+
+  if args.dry_run {
+    let json = serde_json::to_string_pretty(&commands)?;
+    match &args.out {
+      Some(path) => fs::write(path, json + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?,
+      None => println!("{}", json),
+    }
+
+    return Ok(());
+  }
+
+  let client = ClientBuilder::new()
+    .token(env::var("TOKEN").context("TOKEN has to be set to deploy commands")?)
+    .build();
 
   let app_id = client
     .current_user_application()
@@ -177,12 +253,446 @@ async fn main() -> anyhow::Result<()> {
     .model()
     .await?
     .id;
+  let interaction = client.interaction(app_id);
 
-  client
-    .interaction(app_id)
-    .set_global_commands(&[roles_command, persist_command, config_command])
-    .exec()
-    .await?;
+  if args.diff {
+    let registered = match args.guild_id {
+      Some(guild_id) => {
+        interaction
+          .guild_commands(guild_id)
+          .with_localizations(true)
+          .exec()
+          .await?
+          .models()
+          .await?
+      }
+      None => {
+        interaction
+          .global_commands()
+          .with_localizations(true)
+          .exec()
+          .await?
+          .models()
+          .await?
+      }
+    };
+
+    let changes = diff(&registered, &commands)?;
+    if changes.is_empty() {
+      println!("Registered commands are already up to date");
+      return Ok(());
+    }
+    println!("{}", changes.join("\n"));
+  }
+
+  match args.guild_id {
+    Some(guild_id) => {
+      interaction
+        .set_guild_commands(guild_id, &commands)
+        .exec()
+        .await?;
+      println!("Deployed {} commands to guild {}", commands.len(), guild_id);
+    }
+    None => {
+      interaction.set_global_commands(&commands).exec().await?;
+      println!("Deployed {} global commands", commands.len());
+    }
+  }
 
   Ok(())
 }
+
+/// Lines describing how `new` differs from `registered`, one per added or removed command and one
+/// per changed field of a command present in both.
+fn diff(registered: &[Command], new: &[Command]) -> anyhow::Result<Vec<String>> {
+  let flatten_all = |commands: &[Command]| {
+    commands
+      .iter()
+      .map(|command| {
+        let mut fields = BTreeMap::new();
+        flatten("", &serde_json::to_value(command)?, &mut fields);
+        Ok((command.name.clone(), fields))
+      })
+      .collect::<anyhow::Result<BTreeMap<_, _>>>()
+  };
+  let registered = flatten_all(registered)?;
+  let new = flatten_all(new)?;
+
+  let mut lines = Vec::new();
+  for name in registered.keys().filter(|name| !new.contains_key(*name)) {
+    lines.push(format!("- /{}", name));
+  }
+  for (name, fields) in &new {
+    let old_fields = match registered.get(name) {
+      Some(old_fields) => old_fields,
+      None => {
+        lines.push(format!("+ /{}", name));
+        continue;
+      }
+    };
+
+    let paths = old_fields
+      .keys()
+      .chain(fields.keys())
+      .collect::<BTreeSet<_>>();
+    let changes = paths
+      .into_iter()
+      .filter_map(|path| match (old_fields.get(path), fields.get(path)) {
+        (old, new) if old == new => None,
+        (Some(old), Some(new)) => Some(format!("    {}: {} -> {}", path, old, new)),
+        (Some(old), None) => Some(format!("    - {}: {}", path, old)),
+        (None, Some(new)) => Some(format!("    + {}: {}", path, new)),
+        (None, None) => None,
+      })
+      .collect::<Vec<_>>();
+
+    if !changes.is_empty() {
+      lines.push(format!("~ /{}", name));
+      lines.extend(changes);
+    }
+  }
+
+  Ok(lines)
+}
+
+/// Collect every leaf of `value` under a dotted path. Options and choices are keyed by their name
+/// rather than their position, and empty or null values are left out, since Discord doesn't
+/// always return them the way they were sent.
+fn flatten(path: &str, value: &Value, fields: &mut BTreeMap<String, Value>) {
+  let join = |key: &str| {
+    if path.is_empty() {
+      key.to_string()
+    } else {
+      format!("{}.{}", path, key)
+    }
+  };
+
+  match value {
+    Value::Object(map) => {
+      for (key, value) in map {
+        if path.is_empty() && GENERATED_FIELDS.contains(&key.as_str()) {
+          continue;
+        }
+        flatten(&join(key), value, fields);
+      }
+    }
+    Value::Array(items) => {
+      for (idx, item) in items.iter().enumerate() {
+        let key = item
+          .get("name")
+          .and_then(Value::as_str)
+          .map(str::to_string)
+          .unwrap_or_else(|| idx.to_string());
+        flatten(&join(&key), item, fields);
+      }
+    }
+    Value::Null => {}
+    value => {
+      fields.insert(path.to_string(), value.clone());
+    }
+  }
+}
+
+fn roles_command(tr: &Translations) -> anyhow::Result<Command> {
+  Ok(
+    CommandBuilder::new(
+      tr.name("cmd::roles:name", manifest::roles::NAME)?,
+      tr.get("cmd::roles:desc"),
+      CommandType::ChatInput,
+    )
+    .dm_permission(false)
+    .name_localizations(tr.localizations("cmd::roles:name"))
+    .description_localizations(tr.localizations("cmd::roles:desc"))
+    .build(),
+  )
+}
+
+fn persist_command(tr: &Translations) -> anyhow::Result<Command> {
+  Ok(
+    CommandBuilder::new(
+      tr.name("cmd::persist:name", manifest::persist::NAME)?,
+      tr.get("cmd::persist:desc"),
+      CommandType::ChatInput,
+    )
+    .dm_permission(false)
+    .default_member_permissions(Permissions::MANAGE_ROLES)
+    .option(CommandOption::String(ChoiceCommandOptionData {
+      name: tr.name("cmd::persist::content:name", manifest::persist::CONTENT)?,
+      description: tr.get("cmd::persist::content:desc"),
+      name_localizations: Some(tr.localizations("cmd::persist::content:name")),
+      description_localizations: Some(tr.localizations("cmd::persist::content:desc")),
+      ..Default::default()
+    }))
+    .name_localizations(tr.localizations("cmd::persist:name"))
+    .description_localizations(tr.localizations("cmd::persist:desc"))
+    .build(),
+  )
+}
+
+fn config_command(tr: &Translations) -> anyhow::Result<Command> {
+  Ok(
+    CommandBuilder::new(
+      tr.name("cmd::config:name", manifest::config::NAME)?,
+      tr.get("cmd::config:desc"),
+      CommandType::ChatInput,
+    )
+    .dm_permission(false)
+    .default_member_permissions(Permissions::MANAGE_ROLES)
+    .option(CommandOption::SubCommand(OptionsCommandOptionData {
+      name: tr.name("cmd::config::add:name", manifest::config::add::NAME)?,
+      description: tr.get("cmd::config::add:desc"),
+      options: vec![
+        CommandOption::Role(BaseCommandOptionData {
+          name: tr.name("cmd::config::add::role:name", manifest::config::add::ROLE)?,
+          description: tr.get("cmd::config::add::role:desc"),
+          required: true,
+          name_localizations: Some(tr.localizations("cmd::config::add::role:name")),
+          description_localizations: Some(tr.localizations("cmd::config::add::role:desc")),
+        }),
+        CommandOption::String(ChoiceCommandOptionData {
+          name: tr.name("cmd::config::add::label:name", manifest::config::add::LABEL)?,
+          description: tr.get("cmd::config::add::label:desc"),
+          name_localizations: Some(tr.localizations("cmd::config::add::label:name")),
+          description_localizations: Some(tr.localizations("cmd::config::add::label:desc")),
+          ..Default::default()
+        }),
+        CommandOption::String(ChoiceCommandOptionData {
+          name: tr.name(
+            "cmd::config::add::description:name",
+            manifest::config::add::DESCRIPTION,
+          )?,
+          description: tr.get("cmd::config::add::description:desc"),
+          name_localizations: Some(tr.localizations("cmd::config::add::description:name")),
+          description_localizations: Some(tr.localizations("cmd::config::add::description:desc")),
+          ..Default::default()
+        }),
+      ],
+      name_localizations: Some(tr.localizations("cmd::config::add:name")),
+      description_localizations: Some(tr.localizations("cmd::config::add:desc")),
+    }))
+    .option(CommandOption::SubCommand(OptionsCommandOptionData {
+      name: tr.name("cmd::config::remove:name", manifest::config::remove::NAME)?,
+      description: tr.get("cmd::config::remove:desc"),
+      options: vec![CommandOption::Role(BaseCommandOptionData {
+        name: tr.name(
+          "cmd::config::remove::role:name",
+          manifest::config::remove::ROLE,
+        )?,
+        description: tr.get("cmd::config::remove::role:desc"),
+        required: true,
+        name_localizations: Some(tr.localizations("cmd::config::remove::role:name")),
+        description_localizations: Some(tr.localizations("cmd::config::remove::role:desc")),
+      })],
+      name_localizations: Some(tr.localizations("cmd::config::remove:name")),
+      description_localizations: Some(tr.localizations("cmd::config::remove:desc")),
+    }))
+    .option(CommandOption::SubCommand(OptionsCommandOptionData {
+      name: tr.name(
+        "cmd::config::translate:name",
+        manifest::config::translate::NAME,
+      )?,
+      description: tr.get("cmd::config::translate:desc"),
+      options: vec![
+        CommandOption::Role(BaseCommandOptionData {
+          name: tr.name(
+            "cmd::config::translate::role:name",
+            manifest::config::translate::ROLE,
+          )?,
+          description: tr.get("cmd::config::translate::role:desc"),
+          required: true,
+          name_localizations: Some(tr.localizations("cmd::config::translate::role:name")),
+          description_localizations: Some(tr.localizations("cmd::config::translate::role:desc")),
+        }),
+        CommandOption::String(ChoiceCommandOptionData {
+          name: tr.name(
+            "cmd::config::translate::locale:name",
+            manifest::config::translate::LOCALE,
+          )?,
+          description: tr.get("cmd::config::translate::locale:desc"),
+          required: true,
+          name_localizations: Some(tr.localizations("cmd::config::translate::locale:name")),
+          description_localizations: Some(tr.localizations("cmd::config::translate::locale:desc")),
+          ..Default::default()
+        }),
+        CommandOption::String(ChoiceCommandOptionData {
+          name: tr.name(
+            "cmd::config::translate::label:name",
+            manifest::config::translate::LABEL,
+          )?,
+          description: tr.get("cmd::config::translate::label:desc"),
+          required: true,
+          name_localizations: Some(tr.localizations("cmd::config::translate::label:name")),
+          description_localizations: Some(tr.localizations("cmd::config::translate::label:desc")),
+          ..Default::default()
+        }),
+        CommandOption::String(ChoiceCommandOptionData {
+          name: tr.name(
+            "cmd::config::translate::description:name",
+            manifest::config::translate::DESCRIPTION,
+          )?,
+          description: tr.get("cmd::config::translate::description:desc"),
+          name_localizations: Some(tr.localizations("cmd::config::translate::description:name")),
+          description_localizations: Some(
+            tr.localizations("cmd::config::translate::description:desc"),
+          ),
+          ..Default::default()
+        }),
+      ],
+      name_localizations: Some(tr.localizations("cmd::config::translate:name")),
+      description_localizations: Some(tr.localizations("cmd::config::translate:desc")),
+    }))
+    .option(CommandOption::SubCommand(OptionsCommandOptionData {
+      name: tr.name("cmd::config::log:name", manifest::config::log::NAME)?,
+      description: tr.get("cmd::config::log:desc"),
+      options: vec![CommandOption::Channel(ChannelCommandOptionData {
+        channel_types: vec![ChannelType::GuildText],
+        name: tr.name(
+          "cmd::config::log::channel:name",
+          manifest::config::log::CHANNEL,
+        )?,
+        description: tr.get("cmd::config::log::channel:desc"),
+        required: false,
+        name_localizations: Some(tr.localizations("cmd::config::log::channel:name")),
+        description_localizations: Some(tr.localizations("cmd::config::log::channel:desc")),
+      })],
+      name_localizations: Some(tr.localizations("cmd::config::log:name")),
+      description_localizations: Some(tr.localizations("cmd::config::log:desc")),
+    }))
+    .option(CommandOption::SubCommand(OptionsCommandOptionData {
+      name: tr.name(
+        "cmd::config::language:name",
+        manifest::config::language::NAME,
+      )?,
+      description: tr.get("cmd::config::language:desc"),
+      options: vec![CommandOption::String(ChoiceCommandOptionData {
+        name: tr.name(
+          "cmd::config::language::locale:name",
+          manifest::config::language::LOCALE,
+        )?,
+        description: tr.get("cmd::config::language::locale:desc"),
+        choices: tr
+          .locales
+          .keys()
+          .map(|locale| CommandOptionChoice::String {
+            name: tr.get_in(locale, "msg::language:name"),
+            name_localizations: None,
+            value: locale.clone(),
+          })
+          .collect(),
+        name_localizations: Some(tr.localizations("cmd::config::language::locale:name")),
+        description_localizations: Some(tr.localizations("cmd::config::language::locale:desc")),
+        ..Default::default()
+      })],
+      name_localizations: Some(tr.localizations("cmd::config::language:name")),
+      description_localizations: Some(tr.localizations("cmd::config::language:desc")),
+    }))
+    .name_localizations(tr.localizations("cmd::config:name"))
+    .description_localizations(tr.localizations("cmd::config:desc"))
+    .build(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const I18N_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/i18n");
+  const SNAPSHOT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots");
+
+  /// Compare `command` to `snapshots/<name>.json`. Run with `UPDATE_SNAPSHOTS=1` to write the
+  /// current output instead, after checking that the change is intended.
+  fn assert_snapshot(name: &str, command: &Command) {
+    let path = Path::new(SNAPSHOT_DIR).join(format!("{}.json", name));
+    let actual = serde_json::to_value(command).unwrap();
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+      fs::create_dir_all(SNAPSHOT_DIR).unwrap();
+      fs::write(&path, serde_json::to_string_pretty(&actual).unwrap() + "\n").unwrap();
+      return;
+    }
+
+    let expected: Value = serde_json::from_str(
+      &fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("Failed to read {}: {}", path.display(), error)),
+    )
+    .unwrap();
+
+    assert_eq!(
+      actual,
+      expected,
+      "{} is out of date, run the tests with UPDATE_SNAPSHOTS=1 if the change is intended",
+      path.display()
+    );
+  }
+
+  #[test]
+  fn roles_command_snapshot() {
+    let tr = Translations::load(I18N_DIR).unwrap();
+    assert_snapshot("roles", &roles_command(&tr).unwrap());
+  }
+
+  #[test]
+  fn persist_command_snapshot() {
+    let tr = Translations::load(I18N_DIR).unwrap();
+    assert_snapshot("persist", &persist_command(&tr).unwrap());
+  }
+
+  #[test]
+  fn config_command_snapshot() {
+    let tr = Translations::load(I18N_DIR).unwrap();
+    assert_snapshot("config", &config_command(&tr).unwrap());
+  }
+
+  #[test]
+  fn rejects_keys_missing_from_the_default_locale() {
+    let dir = std::env::temp_dir().join(format!("roles-i18n-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join("en-US.json"),
+      r#"{ "a#one": "1", "a#other": "n", "b": "b" }"#,
+    )
+    .unwrap();
+    fs::write(
+      dir.join("pl.json"),
+      r#"{ "a#one": "1", "a#zero": "0", "a#other": "n", "c": "c" }"#,
+    )
+    .unwrap();
+
+    let error = Translations::load(&dir).err().unwrap().to_string();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(error.contains("pl: extra key a#zero"), "{}", error);
+    assert!(error.contains("pl: extra key c"), "{}", error);
+    assert!(error.contains("pl: missing key b"), "{}", error);
+  }
+
+  #[test]
+  fn referenced_keys_exist_in_every_locale() {
+    let tr = Translations::load(I18N_DIR).unwrap();
+    roles_command(&tr).unwrap();
+    persist_command(&tr).unwrap();
+    config_command(&tr).unwrap();
+
+    let mut missing = Vec::new();
+    for entry in fs::read_dir(I18N_DIR).unwrap() {
+      let path = entry.unwrap().path();
+      if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+        continue;
+      }
+
+      let texts: HashMap<String, String> =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+      for key in tr.used.borrow().iter() {
+        if !texts.contains_key(key) {
+          missing.push(format!("{}: {}", path.display(), key));
+        }
+      }
+    }
+
+    assert!(!tr.used.borrow().is_empty());
+    assert!(
+      missing.is_empty(),
+      "Missing translations:\n{}",
+      missing.join("\n")
+    );
+  }
+}
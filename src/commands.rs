@@ -5,48 +5,153 @@ use twilight_model::{
     interaction::{
       application_command::{CommandData, CommandDataOption, CommandOptionValue},
       message_component::MessageComponentInteractionData,
+      InteractionData,
     },
   },
+  channel::message::MessageFlags,
   gateway::payload::incoming::InteractionCreate,
-  http::interaction::InteractionResponse,
+  http::interaction::{InteractionResponse, InteractionResponseType},
 };
+use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::State;
+use crate::{
+  util::{
+    change_source::ChangeSource,
+    i18n::{localize, Locales},
+  },
+  State,
+};
 
 pub mod config;
+pub mod manifest;
 pub mod roles;
 
+/// Loading response for handlers that make several requests before they can answer, which under
+/// rate limits could run past Discord's 3 second deadline. Their result is edited in afterwards.
+pub fn deferred_response(interaction: &InteractionCreate) -> Option<InteractionResponse> {
+  let new_message = |ephemeral: bool| {
+    let data = if ephemeral {
+      InteractionResponseDataBuilder::new()
+        .flags(MessageFlags::EPHEMERAL)
+        .build()
+    } else {
+      InteractionResponseDataBuilder::new().build()
+    };
+
+    Some(InteractionResponse {
+      kind: InteractionResponseType::DeferredChannelMessageWithSource,
+      data: Some(data),
+    })
+  };
+
+  match &interaction.data {
+    Some(InteractionData::ApplicationCommand(command)) => {
+      match (command.kind, command.name.as_str()) {
+        (CommandType::ChatInput, manifest::roles::NAME) => new_message(true),
+        (CommandType::ChatInput, manifest::config::NAME) => match command.options.first() {
+          Some(CommandDataOption { name, .. }) if name == manifest::config::add::NAME => {
+            new_message(false)
+          }
+          _ => None,
+        },
+        _ => None,
+      }
+    }
+    Some(InteractionData::MessageComponent(component)) => {
+      match (component.component_type, component.custom_id.as_str()) {
+        (ComponentType::SelectMenu, _) => Some(InteractionResponse {
+          kind: InteractionResponseType::DeferredUpdateMessage,
+          data: None,
+        }),
+        (ComponentType::Button, manifest::components::SELECT_ROLES) => new_message(true),
+        _ => None,
+      }
+    }
+    _ => None,
+  }
+}
+
 #[tracing::instrument(ret, skip_all)]
 pub async fn handle_command(
   state: State,
-  command: &Box<CommandData>,
-  interaction: impl AsRef<InteractionCreate>,
+  command: &CommandData,
+  interaction: &InteractionCreate,
+  locales: &Locales,
 ) -> anyhow::Result<InteractionResponse> {
-  let interaction = interaction.as_ref();
   match (command.kind, command.name.as_str()) {
-    (CommandType::ChatInput, "roles") => {
+    (CommandType::ChatInput, manifest::roles::NAME) => {
       roles::exec(
         state,
         interaction.guild_id.unwrap(),
-        interaction.author_id().unwrap(),
+        &interaction.member.as_ref().unwrap().roles,
+        ChangeSource::Menu,
+        locales,
       )
       .await
     }
-    (CommandType::ChatInput, "persist") => roles::persist(command),
-    (CommandType::ChatInput, "config") => match command.options.get(0) {
+    (CommandType::ChatInput, manifest::persist::NAME) => roles::persist(command, locales),
+    (CommandType::ChatInput, manifest::config::NAME) => match command.options.first() {
+      Some(CommandDataOption {
+        name,
+        value: CommandOptionValue::SubCommand(options),
+      }) if name == manifest::config::add::NAME => {
+        config::add::exec(
+          state,
+          options,
+          interaction.guild_id.unwrap(),
+          interaction.author_id().unwrap(),
+          locales,
+        )
+        .await
+      }
       Some(CommandDataOption {
         name,
         value: CommandOptionValue::SubCommand(options),
-      }) if name == "add" => config::add::exec(state, options, interaction.guild_id.unwrap()).await,
+      }) if name == manifest::config::remove::NAME => {
+        config::remove::exec(
+          state,
+          options,
+          interaction.guild_id.unwrap(),
+          interaction.author_id().unwrap(),
+          locales,
+        )
+        .await
+      }
+      Some(CommandDataOption {
+        name,
+        value: CommandOptionValue::SubCommand(options),
+      }) if name == manifest::config::translate::NAME => {
+        config::translate::exec(
+          state,
+          options,
+          interaction.guild_id.unwrap(),
+          interaction.author_id().unwrap(),
+          locales,
+        )
+        .await
+      }
       Some(CommandDataOption {
         name,
         value: CommandOptionValue::SubCommand(options),
-      }) if name == "remove" => {
-        config::remove::exec(state, options, interaction.guild_id.unwrap()).await
+      }) if name == manifest::config::log::NAME => {
+        config::log::exec(state, options, interaction.guild_id.unwrap(), locales).await
       }
-      _ => unreachable!(),
+      Some(CommandDataOption {
+        name,
+        value: CommandOptionValue::SubCommand(options),
+      }) if name == manifest::config::language::NAME => {
+        config::language::exec(
+          state,
+          options,
+          interaction.guild_id.unwrap(),
+          interaction.guild_locale.as_deref(),
+          locales,
+        )
+        .await
+      }
+      _ => anyhow::bail!(localize(&locales.user, "msg::error:unsupported")),
     },
-    _ => unreachable!(),
+    _ => anyhow::bail!(localize(&locales.user, "msg::error:unsupported")),
   }
 }
 
@@ -55,19 +160,23 @@ pub async fn handle_menu(
   state: State,
   interaction: Box<InteractionCreate>,
   component: &MessageComponentInteractionData,
+  locales: &Locales,
 ) -> anyhow::Result<InteractionResponse> {
   match (component.component_type, component.custom_id.as_str()) {
-    (ComponentType::SelectMenu, "roleMenu") => {
-      roles::select::exec(state, interaction, component).await
-    }
-    (ComponentType::Button, "selectRoles") => {
+    (ComponentType::SelectMenu, custom_id) => match ChangeSource::from_menu_id(custom_id) {
+      Some(source) => roles::select::exec(state, interaction, component, source, locales).await,
+      None => anyhow::bail!(localize(&locales.user, "msg::error:unsupported")),
+    },
+    (ComponentType::Button, manifest::components::SELECT_ROLES) => {
       roles::exec(
         state,
         interaction.guild_id.unwrap(),
-        interaction.author_id().unwrap(),
+        &interaction.member.as_ref().unwrap().roles,
+        ChangeSource::Panel,
+        locales,
       )
       .await
     }
-    _ => unreachable!(),
+    _ => anyhow::bail!(localize(&locales.user, "msg::error:unsupported")),
   }
 }
@@ -1,2 +1,5 @@
 pub mod add;
+pub mod language;
+pub mod log;
 pub mod remove;
+pub mod translate;
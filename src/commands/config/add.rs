@@ -1,120 +1,140 @@
 use twilight_model::{
   application::interaction::application_command::{CommandDataOption, CommandOptionValue},
   http::interaction::{InteractionResponse, InteractionResponseType},
-  id::{marker::GuildMarker, Id},
+  id::{
+    marker::{GuildMarker, RoleMarker, UserMarker},
+    Id,
+  },
 };
 use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::State;
+use crate::{
+  commands::manifest,
+  util::{
+    cache,
+    i18n::{localize, localize_with, Locales},
+  },
+  State,
+};
 
 #[tracing::instrument(ret, skip_all)]
 pub async fn exec(
   state: State,
   options: &[CommandDataOption],
   guild_id: Id<GuildMarker>,
+  user_id: Id<UserMarker>,
+  locales: &Locales,
 ) -> anyhow::Result<InteractionResponse> {
   let p_role = options
     .iter()
     .find_map(|option| match option.value {
-      CommandOptionValue::Role(role) if option.name == "role" => Some(role),
+      CommandOptionValue::Role(role) if option.name == manifest::config::add::ROLE => Some(role),
       _ => None,
     })
     .unwrap();
   let p_label = options.iter().find_map(|option| match &option.value {
-    CommandOptionValue::String(label) if option.name == "label" => Some(label),
+    CommandOptionValue::String(label) if option.name == manifest::config::add::LABEL => Some(label),
     _ => None,
   });
   let p_description = options.iter().find_map(|option| match &option.value {
-    CommandOptionValue::String(label) if option.name == "description" => Some(label),
+    CommandOptionValue::String(label) if option.name == manifest::config::add::DESCRIPTION => {
+      Some(label)
+    }
     _ => None,
   });
 
-  let me = state
-    .client
-    .guild_member(guild_id, state.app_id.cast())
-    .exec()
-    .await?
-    .model()
-    .await?;
-
-  let guild_roles = state.client.roles(guild_id).exec().await?.model().await?;
+  let guild_roles = cache::guild_roles(&state, guild_id).await?;
 
   let found = guild_roles
     .iter()
     .find(|role| role.id == p_role)
-    .ok_or_else(|| anyhow::anyhow!("Couldn't find the selected role."))?;
+    .ok_or_else(|| anyhow::anyhow!(localize(&locales.user, "msg::config::add:notFound")))?;
+  let role_mention = format!("<@&{}>", found.id);
 
   anyhow::ensure!(
     !found.managed,
-    "You cannot add a managed role <@&{}> to selfroles.",
-    found.id
+    localize_with(
+      &locales.user,
+      "msg::config::add:managed",
+      &[("role", &role_mention)]
+    )
   );
 
   anyhow::ensure!(
     found.id.cast() != guild_id,
-    "You cannot add @everyone role to selfroles."
+    localize(&locales.user, "msg::config::add:everyone")
   );
 
-  let my_highest = guild_roles
-    .iter()
-    .filter(|role| me.roles.contains(&role.id))
-    .max();
+  let highest = |my_roles: &[Id<RoleMarker>]| {
+    guild_roles
+      .iter()
+      .filter(|role| my_roles.contains(&role.id))
+      .max()
+  };
+
+  let mut my_highest = highest(&cache::bot_roles(&state, guild_id, false).await?);
+  if my_highest <= Some(found) {
+    // The cached roles of the bot may be missing one it was given since, so only refuse after
+    // checking with Discord.
+    my_highest = highest(&cache::bot_roles(&state, guild_id, true).await?);
+  }
+
+  let highest_mention = format!(
+    "<@&{}>",
+    my_highest.map(|r| r.id).unwrap_or(guild_id.cast())
+  );
 
   anyhow::ensure!(
     my_highest > Some(found),
-    "You cannot add role <@&{}> to selfroles as it is higher than, or equally high as my highest role <@&{}>.",
-    found.id,
-    my_highest.map(|r| r.id).unwrap_or(guild_id.cast())
+    localize_with(
+      &locales.user,
+      "msg::config::add:tooHigh",
+      &[("role", &role_mention), ("highest", &highest_mention)]
+    )
   );
 
-  let guild_id = guild_id.to_string();
-  let role_id = found.id.to_string();
   let role_name = p_label.unwrap_or(&found.name);
 
-  let role_configured = sqlx::query!(
-    r#"
-      SELECT EXISTS(
-        SELECT 1 FROM roles WHERE role_id = ?
-      ) AS "existing!: i32"
-    "#,
-    role_id
-  )
-  .fetch_one(&state.pool)
-  .await?;
-
-  let verb;
-  match role_configured.existing {
-    0 => verb = "added",
-    1 => verb = "updated",
-    _ => unreachable!(),
-  }
+  let role_configured = state.store.exists(guild_id, found.id).await?;
+
+  let (response_key, log_key) = if role_configured {
+    ("msg::config::add:updated", "msg::log:configUpdated")
+  } else {
+    ("msg::config::add:added", "msg::log:configAdded")
+  };
 
-  sqlx::query!(
-    r#"
-      INSERT INTO roles VALUES (?, ?, ?, ?)
-      ON CONFLICT (role_id) DO UPDATE SET
-        label = excluded.label,
-        description = excluded.description
-      ;
-    "#,
+  state
+    .store
+    .upsert(
+      guild_id,
+      found.id,
+      role_name,
+      p_description.map(String::as_str),
+    )
+    .await?;
+
+  state.log_channel.post(
     guild_id,
-    role_id,
-    role_name,
-    p_description
-  )
-  .execute(&state.pool)
-  .await?;
+    localize_with(
+      &locales.guild,
+      log_key,
+      &[
+        ("user", &format!("<@{}>", user_id)),
+        ("role", &role_mention),
+      ],
+    ),
+  );
 
   let response = InteractionResponseDataBuilder::new()
-    .content(format!(
-      "Successfully {} selfrole <@&{}>.",
-      verb.to_string(),
-      found.id
+    .content(localize_with(
+      &locales.guild,
+      response_key,
+      &[("role", &role_mention)],
     ))
     .build();
 
-  return Ok(InteractionResponse {
+  Ok(InteractionResponse {
     data: Some(response),
     kind: InteractionResponseType::ChannelMessageWithSource,
-  });
+  })
 }
@@ -0,0 +1,77 @@
+use twilight_model::{
+  application::interaction::application_command::{CommandDataOption, CommandOptionValue},
+  http::interaction::{InteractionResponse, InteractionResponseType},
+  id::{marker::GuildMarker, Id},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+  commands::manifest,
+  util::i18n::{format_list_or, localize, localize_with, Locales, LOCALES},
+  State,
+};
+
+#[tracing::instrument(ret, skip_all)]
+pub async fn exec(
+  state: State,
+  options: &[CommandDataOption],
+  guild_id: Id<GuildMarker>,
+  discord_locale: Option<&str>,
+  locales: &Locales,
+) -> anyhow::Result<InteractionResponse> {
+  let p_locale = options.iter().find_map(|option| match &option.value {
+    CommandOptionValue::String(locale) if option.name == manifest::config::language::LOCALE => {
+      Some(locale)
+    }
+    _ => None,
+  });
+
+  if let Some(locale) = p_locale {
+    anyhow::ensure!(
+      LOCALES.contains(&locale.as_str()),
+      localize_with(
+        &locales.user,
+        "msg::config::language:unsupported",
+        &[
+          ("locale", locale),
+          (
+            "languages",
+            &format_list_or(
+              &locales.user,
+              LOCALES
+                .iter()
+                .map(|locale| localize(locale, "msg::language:name")),
+            ),
+          ),
+        ]
+      )
+    );
+  }
+
+  state
+    .store
+    .set_guild_locale(guild_id, p_locale.map(String::as_str))
+    .await?;
+
+  // Confirm in the language public messages are going to use from now on.
+  let content = match p_locale {
+    Some(locale) => localize_with(
+      locale,
+      "msg::config::language:set",
+      &[("language", &localize(locale, "msg::language:name"))],
+    ),
+    None => localize(
+      discord_locale.unwrap_or(&locales.user),
+      "msg::config::language:reset",
+    ),
+  };
+
+  let response = InteractionResponseDataBuilder::new()
+    .content(content)
+    .build();
+
+  Ok(InteractionResponse {
+    data: Some(response),
+    kind: InteractionResponseType::ChannelMessageWithSource,
+  })
+}
@@ -0,0 +1,47 @@
+use twilight_model::{
+  application::interaction::application_command::{CommandDataOption, CommandOptionValue},
+  http::interaction::{InteractionResponse, InteractionResponseType},
+  id::{marker::GuildMarker, Id},
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+  commands::manifest,
+  util::i18n::{localize, localize_with, Locales},
+  State,
+};
+
+#[tracing::instrument(ret, skip_all)]
+pub async fn exec(
+  state: State,
+  options: &[CommandDataOption],
+  guild_id: Id<GuildMarker>,
+  locales: &Locales,
+) -> anyhow::Result<InteractionResponse> {
+  let p_channel = options.iter().find_map(|option| match option.value {
+    CommandOptionValue::Channel(channel) if option.name == manifest::config::log::CHANNEL => {
+      Some(channel)
+    }
+    _ => None,
+  });
+
+  state.store.set_log_channel(guild_id, p_channel).await?;
+
+  let content = match p_channel {
+    Some(channel) => localize_with(
+      &locales.guild,
+      "msg::config::log:enabled",
+      &[("channel", &format!("<#{}>", channel))],
+    ),
+    None => localize(&locales.guild, "msg::config::log:disabled"),
+  };
+
+  let response = InteractionResponseDataBuilder::new()
+    .content(content)
+    .build();
+
+  Ok(InteractionResponse {
+    data: Some(response),
+    kind: InteractionResponseType::ChannelMessageWithSource,
+  })
+}
@@ -1,44 +1,66 @@
 use twilight_model::{
   application::interaction::application_command::{CommandDataOption, CommandOptionValue},
   http::interaction::{InteractionResponse, InteractionResponseType},
-  id::{marker::GuildMarker, Id},
+  id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+  },
 };
 use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::State;
+use crate::{
+  commands::manifest,
+  util::i18n::{localize_with, Locales},
+  State,
+};
 
 #[tracing::instrument(ret, skip_all)]
 pub async fn exec(
   state: State,
   options: &[CommandDataOption],
   guild_id: Id<GuildMarker>,
+  user_id: Id<UserMarker>,
+  locales: &Locales,
 ) -> anyhow::Result<InteractionResponse> {
   let p_role = options
     .iter()
     .find_map(|option| match option.value {
-      CommandOptionValue::Role(role) if option.name == "role" => Some(role),
+      CommandOptionValue::Role(role) if option.name == manifest::config::remove::ROLE => Some(role),
       _ => None,
     })
     .unwrap();
 
-  let guild_id = guild_id.to_string();
-  let role_id = p_role.to_string();
-  let count: Option<_> = sqlx::query!(
-    "DELETE FROM roles WHERE guild_id = ? AND role_id = ? RETURNING *",
-    guild_id,
-    role_id
-  )
-  .fetch_optional(&state.pool)
-  .await?;
+  let removed = state.store.remove(guild_id, p_role).await?;
+
+  let role_mention = format!("<@&{}>", p_role);
 
   anyhow::ensure!(
-    !count.is_none(),
-    "Role <@&{}> is not a selfrole, so it cannot be removed.",
-    p_role
+    removed,
+    localize_with(
+      &locales.user,
+      "msg::config::remove:notSelfrole",
+      &[("role", &role_mention)]
+    )
+  );
+
+  state.log_channel.post(
+    guild_id,
+    localize_with(
+      &locales.guild,
+      "msg::log:configRemoved",
+      &[
+        ("user", &format!("<@{}>", user_id)),
+        ("role", &role_mention),
+      ],
+    ),
   );
 
   let response = InteractionResponseDataBuilder::new()
-    .content(format!("Successfully removed selfrole <@&{}>", role_id))
+    .content(localize_with(
+      &locales.guild,
+      "msg::config::remove:removed",
+      &[("role", &role_mention)],
+    ))
     .build();
 
   Ok(InteractionResponse {
@@ -46,3 +68,59 @@ pub async fn exec(
     kind: InteractionResponseType::ChannelMessageWithSource,
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::store::{InMemoryStore, SelfRoleStore};
+  use std::sync::Arc;
+
+  fn options(role_id: u64) -> Vec<CommandDataOption> {
+    vec![CommandDataOption {
+      name: manifest::config::remove::ROLE.to_string(),
+      value: CommandOptionValue::Role(Id::new(role_id)),
+    }]
+  }
+
+  #[tokio::test]
+  async fn removes_selfrole_and_its_labels() {
+    let store = Arc::new(InMemoryStore::default());
+    store
+      .upsert(Id::new(1), Id::new(10), "One", None)
+      .await
+      .unwrap();
+    store
+      .upsert_label(Id::new(10), "pl", "Jeden", None)
+      .await
+      .unwrap();
+    let state = State::with_store(store.clone());
+    let locales = Locales {
+      user: "en-US".to_string(),
+      guild: "en-US".to_string(),
+    };
+
+    let response = exec(
+      state.clone(),
+      &options(10),
+      Id::new(1),
+      Id::new(2),
+      &locales,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+      response.data.unwrap().content.as_deref(),
+      Some("Successfully removed selfrole <@&10>.")
+    );
+    assert!(!store.exists(Id::new(1), Id::new(10)).await.unwrap());
+    assert!(store.labels_for_guild(Id::new(1)).await.unwrap().is_empty());
+
+    let error = exec(state, &options(10), Id::new(1), Id::new(2), &locales)
+      .await
+      .unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "Role <@&10> is not a selfrole, so it cannot be removed."
+    );
+  }
+}
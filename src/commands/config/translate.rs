@@ -0,0 +1,117 @@
+use icu_locid::LanguageIdentifier;
+use twilight_model::{
+  application::interaction::application_command::{CommandDataOption, CommandOptionValue},
+  http::interaction::{InteractionResponse, InteractionResponseType},
+  id::{
+    marker::{GuildMarker, UserMarker},
+    Id,
+  },
+};
+use twilight_util::builder::InteractionResponseDataBuilder;
+
+use crate::{
+  commands::manifest,
+  util::i18n::{localize_with, Locales},
+  State,
+};
+
+#[tracing::instrument(ret, skip_all)]
+pub async fn exec(
+  state: State,
+  options: &[CommandDataOption],
+  guild_id: Id<GuildMarker>,
+  user_id: Id<UserMarker>,
+  locales: &Locales,
+) -> anyhow::Result<InteractionResponse> {
+  let p_role = options
+    .iter()
+    .find_map(|option| match option.value {
+      CommandOptionValue::Role(role) if option.name == manifest::config::translate::ROLE => {
+        Some(role)
+      }
+      _ => None,
+    })
+    .unwrap();
+  let p_locale = options
+    .iter()
+    .find_map(|option| match &option.value {
+      CommandOptionValue::String(locale) if option.name == manifest::config::translate::LOCALE => {
+        Some(locale)
+      }
+      _ => None,
+    })
+    .unwrap();
+  let p_label = options
+    .iter()
+    .find_map(|option| match &option.value {
+      CommandOptionValue::String(label) if option.name == manifest::config::translate::LABEL => {
+        Some(label)
+      }
+      _ => None,
+    })
+    .unwrap();
+  let p_description = options.iter().find_map(|option| match &option.value {
+    CommandOptionValue::String(description)
+      if option.name == manifest::config::translate::DESCRIPTION =>
+    {
+      Some(description)
+    }
+    _ => None,
+  });
+
+  let role_mention = format!("<@&{}>", p_role);
+
+  // Stored in canonical form, so it lines up with the locales of the ICU fallback chain.
+  let locale = p_locale
+    .parse::<LanguageIdentifier>()
+    .map_err(|_| {
+      anyhow::anyhow!(localize_with(
+        &locales.user,
+        "msg::config::translate:invalidLocale",
+        &[("locale", p_locale)]
+      ))
+    })?
+    .to_string();
+
+  let role_configured = state.store.exists(guild_id, p_role).await?;
+
+  anyhow::ensure!(
+    role_configured,
+    localize_with(
+      &locales.user,
+      "msg::config::translate:notSelfrole",
+      &[("role", &role_mention)]
+    )
+  );
+
+  state
+    .store
+    .upsert_label(p_role, &locale, p_label, p_description.map(String::as_str))
+    .await?;
+
+  state.log_channel.post(
+    guild_id,
+    localize_with(
+      &locales.guild,
+      "msg::log:configTranslated",
+      &[
+        ("user", &format!("<@{}>", user_id)),
+        ("role", &role_mention),
+        ("locale", &locale),
+      ],
+    ),
+  );
+
+  let response = InteractionResponseDataBuilder::new()
+    .content(localize_with(
+      &locales.guild,
+      "msg::config::translate:saved",
+      &[("role", &role_mention), ("locale", &locale)],
+    ))
+    .build();
+
+  Ok(InteractionResponse {
+    data: Some(response),
+    kind: InteractionResponseType::ChannelMessageWithSource,
+  })
+}
@@ -0,0 +1,52 @@
+//! Names of every command, option and component the bot dispatches on. `deploy` registers the
+//! commands under these same names, and includes this file directly, so it can't depend on
+//! anything else in the crate.
+
+pub mod roles {
+  pub const NAME: &str = "roles";
+}
+
+pub mod persist {
+  pub const NAME: &str = "persist";
+  pub const CONTENT: &str = "content";
+}
+
+pub mod config {
+  pub const NAME: &str = "config";
+
+  pub mod add {
+    pub const NAME: &str = "add";
+    pub const ROLE: &str = "role";
+    pub const LABEL: &str = "label";
+    pub const DESCRIPTION: &str = "description";
+  }
+
+  pub mod remove {
+    pub const NAME: &str = "remove";
+    pub const ROLE: &str = "role";
+  }
+
+  pub mod translate {
+    pub const NAME: &str = "translate";
+    pub const ROLE: &str = "role";
+    pub const LOCALE: &str = "locale";
+    pub const LABEL: &str = "label";
+    pub const DESCRIPTION: &str = "description";
+  }
+
+  pub mod log {
+    pub const NAME: &str = "log";
+    pub const CHANNEL: &str = "channel";
+  }
+
+  pub mod language {
+    pub const NAME: &str = "language";
+    pub const LOCALE: &str = "locale";
+  }
+}
+
+pub mod components {
+  pub const ROLE_MENU: &str = "roleMenu";
+  pub const PANEL_ROLE_MENU: &str = "panelRoleMenu";
+  pub const SELECT_ROLES: &str = "selectRoles";
+}
@@ -8,101 +8,153 @@ use twilight_model::{
   channel::message::MessageFlags,
   http::interaction::{InteractionResponse, InteractionResponseType},
   id::{
-    marker::{GuildMarker, UserMarker},
+    marker::{GuildMarker, RoleMarker},
     Id,
   },
 };
 use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::{RoleData, State};
+use crate::{
+  commands::manifest,
+  util::{
+    cache,
+    change_source::ChangeSource,
+    i18n::{fallback_chain, localize, Locales},
+  },
+  RoleData, State,
+};
 
 pub mod select;
 
+/// Opens the selfrole menu. `member_roles` come with the interaction, so showing which roles the
+/// member already has takes no request.
 #[tracing::instrument(ret, level = "debug", skip_all)]
 pub async fn exec(
   state: State,
   guild_id: Id<GuildMarker>,
-  user_id: Id<UserMarker>,
+  member_roles: &[Id<RoleMarker>],
+  source: ChangeSource,
+  locales: &Locales,
 ) -> anyhow::Result<InteractionResponse> {
-  let guild_id_string = guild_id.to_string();
-  let self_roles: Vec<RoleData> = sqlx::query_as!(
-    RoleData,
-    "SELECT role_id, label, description FROM roles WHERE guild_id = ?",
-    guild_id_string,
-  )
-  .fetch_all(&state.pool)
-  .await?;
-
-  anyhow::ensure!(!self_roles.is_empty(), "Sorry, there are no roles to pick from. Contact server administrator to check if this is intentional.");
-  // anyhow::ensure!(!self_roles.is_empty(), localize(locale, "noRoles"));
-
-  let guild_roles = state.client.roles(guild_id).exec().await?.model().await?;
-  let mut sorted_self_roles = self_roles;
-  sorted_self_roles.sort_unstable_by(|a, b| {
-    let a_role_idx = guild_roles.iter().position(|x| x.id.to_string() == a.role_id).unwrap();
-    let b_role_idx = guild_roles.iter().position(|x| x.id.to_string() == b.role_id).unwrap();
+  let self_roles = state.store.list_for_guild(guild_id).await?;
+
+  anyhow::ensure!(
+    !self_roles.is_empty(),
+    localize(&locales.user, "msg::roles:noRoles")
+  );
+
+  let select = menu(&state, guild_id, self_roles, source, member_roles, locales).await?;
 
-    let a_role = guild_roles.get(a_role_idx).unwrap();
-    let b_role = guild_roles.get(b_role_idx).unwrap();
+  let response = InteractionResponseDataBuilder::new()
+    .components([select])
+    .flags(MessageFlags::EPHEMERAL)
+    .content(localize(&locales.user, "msg::roles:content"))
+    .build();
+
+  Ok(InteractionResponse {
+    data: Some(response),
+    kind: InteractionResponseType::ChannelMessageWithSource,
+  })
+}
+
+/// Select menu listing the selfroles in role hierarchy order, with the ones in `member_roles`
+/// already checked. Labels and descriptions use the closest variant to the member's locale set with
+/// `/config translate`, or the ones from `/config add` when there is none.
+pub async fn menu(
+  state: &State,
+  guild_id: Id<GuildMarker>,
+  self_roles: Vec<RoleData>,
+  source: ChangeSource,
+  member_roles: &[Id<RoleMarker>],
+  locales: &Locales,
+) -> anyhow::Result<Component> {
+  let guild_roles = cache::guild_roles(state, guild_id).await?;
 
-    b_role.partial_cmp(a_role).unwrap_or(std::cmp::Ordering::Equal)
+  // A role deleted while the bot was offline, or over HTTP where deletions aren't received, is
+  // still stored as a selfrole. So is one deleted just now, before `events::role_deleted` ran.
+  let (mut sorted_self_roles, deleted): (Vec<_>, Vec<_>) =
+    self_roles.into_iter().partition(|role| {
+      guild_roles
+        .iter()
+        .any(|guild_role| guild_role.id == role.role_id)
+    });
+  for role in deleted {
+    tracing::info!(
+      "Role {} is gone from guild {}, removing it from the selfroles",
+      role.role_id,
+      guild_id
+    );
+    if let Err(error) = state.store.remove(guild_id, role.role_id).await {
+      tracing::warn!("Failed to remove deleted role {}: {}", role.role_id, error);
+    }
+  }
+  anyhow::ensure!(
+    !sorted_self_roles.is_empty(),
+    localize(&locales.user, "msg::roles:noRoles")
+  );
+
+  sorted_self_roles.sort_unstable_by(|a, b| {
+    let a_role = guild_roles.iter().find(|role| role.id == a.role_id);
+    let b_role = guild_roles.iter().find(|role| role.id == b.role_id);
+
+    b_role
+      .partial_cmp(&a_role)
+      .unwrap_or(std::cmp::Ordering::Equal)
   });
 
-  let member = state
-    .client
-    .guild_member(guild_id, user_id)
-    .exec()
-    .await?
-    .model()
-    .await?;
+  let labels = state.store.labels_for_guild(guild_id).await?;
+  let locale_chain = fallback_chain(&locales.user);
 
-  let select = Component::ActionRow(ActionRow {
+  Ok(Component::ActionRow(ActionRow {
     components: vec![Component::SelectMenu(SelectMenu {
-      custom_id: "roleMenu".to_string(),
+      custom_id: source.menu_id().to_string(),
       disabled: false,
       max_values: Some(sorted_self_roles.len().try_into().unwrap()),
       min_values: Some(0),
-      placeholder: Some("Select your roles".to_string()),
+      placeholder: Some(localize(&locales.user, "msg::roles:placeholder")),
       options: sorted_self_roles
         .into_iter()
-        .map(|role| SelectMenuOption {
-          default: member.roles.contains(&role.role_id.parse().unwrap()),
-          description: role.description,
-          emoji: None,
-          label: role.label,
-          value: role.role_id,
+        .map(|role| {
+          let localized = locale_chain.iter().find_map(|locale| {
+            labels
+              .iter()
+              .find(|label| label.role_id == role.role_id && &label.locale == locale)
+          });
+          let (label, description) = match localized {
+            Some(localized) => (localized.label.clone(), localized.description.clone()),
+            None => (role.label, role.description),
+          };
+
+          SelectMenuOption {
+            default: member_roles.contains(&role.role_id),
+            description,
+            emoji: None,
+            label,
+            value: role.role_id.to_string(),
+          }
         })
         .collect(),
     })],
-  });
-
-  let response = InteractionResponseDataBuilder::new()
-    .components([select])
-    .flags(MessageFlags::EPHEMERAL)
-    .content("Select or deselect all the roles you want from the menu below, and click out of the menu to confirm.")
-    .build();
-
-  Ok(InteractionResponse {
-    data: Some(response),
-    kind: InteractionResponseType::ChannelMessageWithSource,
-  })
+  }))
 }
 
-pub fn persist(command: &Box<CommandData>) -> anyhow::Result<InteractionResponse> {
+pub fn persist(command: &CommandData, locales: &Locales) -> anyhow::Result<InteractionResponse> {
   let p_content = command
     .options
     .iter()
     .find_map(|option| match &option.value {
-      CommandOptionValue::String(content) if option.name == "content" => Some(content),
+      CommandOptionValue::String(content) if option.name == manifest::persist::CONTENT => {
+        Some(content.clone())
+      }
       _ => None,
     });
 
   let button = Component::ActionRow(ActionRow {
     components: vec![Component::Button(Button {
-      custom_id: Some("selectRoles".to_string()),
+      custom_id: Some(manifest::components::SELECT_ROLES.to_string()),
       disabled: false,
       emoji: None,
-      label: Some("Get Roles".to_string()),
+      label: Some(localize(&locales.guild, "msg::persist:button")),
       style: ButtonStyle::Primary,
       url: None,
     })],
@@ -110,7 +162,7 @@ pub fn persist(command: &Box<CommandData>) -> anyhow::Result<InteractionResponse
 
   let response = InteractionResponseDataBuilder::new()
     .components([button])
-    .content(p_content.unwrap_or(&"GET ROLES HERE".to_string()))
+    .content(p_content.unwrap_or_else(|| localize(&locales.guild, "msg::persist:content")))
     .build();
 
   Ok(InteractionResponse {
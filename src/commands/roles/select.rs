@@ -1,80 +1,324 @@
-use std::collections::HashSet;
-use twilight_http::request::AuditLogReason;
+use std::{
+  collections::HashSet,
+  time::{Duration, SystemTime},
+};
+use twilight_http::{api_error::ApiError, error::ErrorType, request::AuditLogReason};
 use twilight_model::{
   application::interaction::message_component::MessageComponentInteractionData,
-  channel::message::MessageFlags,
   gateway::payload::incoming::InteractionCreate,
   http::interaction::{InteractionResponse, InteractionResponseType},
+  id::{marker::RoleMarker, Id},
 };
 use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::{util::i18n::format_list_and, RoleData, State};
+use crate::{
+  commands::roles,
+  util::{
+    change_source::ChangeSource,
+    i18n::{
+      format_list_and, format_timestamp, localize, localize_plural, localize_with, Locales,
+      TimestampStyle,
+    },
+  },
+  RoleData, State,
+};
 
 #[tracing::instrument(ret, level = "debug", skip_all)]
 pub async fn exec(
   state: State,
   interaction: Box<InteractionCreate>,
   component: &MessageComponentInteractionData,
+  source: ChangeSource,
+  locales: &Locales,
 ) -> anyhow::Result<InteractionResponse> {
-  let guild_id = interaction.guild_id.unwrap().to_string();
-  let self_roles: Vec<RoleData> = sqlx::query_as!(
-    RoleData,
-    "SELECT role_id, label, description FROM roles WHERE guild_id = ?",
-    guild_id
+  let guild_id = interaction.guild_id.unwrap();
+  let self_roles = state.store.list_for_guild(guild_id).await?;
+
+  let RoleChanges {
+    previous_roles,
+    added,
+    removed,
+  } = role_changes(
+    &interaction.member.as_ref().unwrap().roles,
+    &self_roles,
+    &component.values,
+  )?;
+
+  let user_id = interaction.author_id().unwrap();
+  let client = &state.client;
+
+  let add_requests = added.iter().map(|&role_id| async move {
+    let result = async {
+      client
+        .add_guild_member_role(guild_id, user_id, role_id)
+        .reason(source.reason())?
+        .exec()
+        .await?;
+
+      anyhow::Ok(())
+    };
+    (role_id, result.await)
+  });
+  let remove_requests = removed.iter().map(|&role_id| async move {
+    let result = async {
+      client
+        .remove_guild_member_role(guild_id, user_id, role_id)
+        .reason(source.reason())?
+        .exec()
+        .await?;
+
+      anyhow::Ok(())
+    };
+    (role_id, result.await)
+  });
+
+  // Every change is attempted even when some fail, like a role moved above the bot in the meantime,
+  // and the member is told which ones didn't go through.
+  let (add_results, remove_results) = futures::future::join(
+    futures::future::join_all(add_requests),
+    futures::future::join_all(remove_requests),
   )
-  .fetch_all(&state.pool)
-  .await?;
+  .await;
 
-  let current_roles = interaction
-    .member
-    .as_ref()
-    .unwrap()
-    .roles
-    .iter()
+  let mut failed = Vec::new();
+  let mut retry_after = None;
+  let mut succeeded = |results: Vec<(Id<RoleMarker>, anyhow::Result<()>)>| {
+    results
+      .into_iter()
+      .filter_map(|(role_id, result)| match result {
+        Ok(()) => Some(role_id),
+        Err(error) => {
+          tracing::warn!(
+            "Failed to change role {} of member {} in guild {}: {}",
+            role_id,
+            user_id,
+            guild_id,
+            error
+          );
+          failed.push(role_id);
+          retry_after = retry_after.max(rate_limit(&error));
+          None
+        }
+      })
+      .collect::<Vec<_>>()
+  };
+  let added = succeeded(add_results);
+  let removed = succeeded(remove_results);
+  tracing::debug!(
+    "Added {:?}, removed {:?} and failed to change {:?} for member {} in guild {}",
+    added,
+    removed,
+    failed,
+    user_id,
+    guild_id
+  );
+
+  // What the member ends up with, which is what the menu shows from now on.
+  let selected_roles = previous_roles
+    .difference(&removed.iter().cloned().collect())
     .cloned()
+    .chain(added.iter().cloned())
     .collect::<HashSet<_>>();
-  let self_roles = self_roles
-    .into_iter()
-    .map(|role| role.role_id.parse())
-    .collect::<Result<HashSet<_>, _>>()?;
-
-  let other_roles = current_roles.difference(&self_roles).cloned().map(Ok);
-  let final_roles = other_roles
-    .chain(component.values.iter().map(|role| role.parse()))
-    .collect::<Result<Vec<_>, _>>()?;
-
-  state
-    .client
-    .update_guild_member(
-      interaction.guild_id.unwrap(),
-      interaction.author_id().unwrap(),
-    )
-    .roles(&final_roles)
-    .reason("self role")?
-    .exec()
-    .await?;
-
-  let mapped = component.values.iter().map(|role| format!("<@&{}>", role));
-  // .collect::<Vec<_>>();
-  let out = if mapped.size_hint().1 == Some(0) {
-    String::from("Cleared your roles.")
+
+  for (key, roles) in [("msg::log:added", added), ("msg::log:removed", removed)] {
+    if roles.is_empty() {
+      continue;
+    }
+
+    let mapped = roles.iter().map(|role| format!("<@&{}>", role));
+    state.log_channel.post(
+      guild_id,
+      localize_with(
+        &locales.guild,
+        key,
+        &[
+          ("user", &format!("<@{}>", user_id)),
+          ("roles", &format_list_and(&locales.guild, mapped)),
+        ],
+      ),
+    );
+  }
+
+  let mapped = selected_roles.iter().map(|role| format!("<@&{}>", role));
+  let mut status = if selected_roles.is_empty() {
+    localize(&locales.user, "msg::roles::select:cleared")
   } else {
-    format!(
-      "Set your roles to {}.",
-      format_list_and(
-        interaction.locale.as_ref().unwrap().parse().unwrap(),
-        mapped
-      )
+    localize_plural(
+      &locales.user,
+      "msg::roles::select:set",
+      selected_roles.len(),
+      &[("roles", &format_list_and(&locales.user, mapped))],
     )
   };
+  if !failed.is_empty() {
+    let mapped = failed.iter().map(|role| format!("<@&{}>", role));
+    let roles = format_list_and(&locales.user, mapped);
+    status.push('\n');
+    status.push_str(&match retry_after {
+      Some(retry_after) => localize_with(
+        &locales.user,
+        "msg::roles::select:rateLimited",
+        &[
+          ("roles", &roles),
+          (
+            "time",
+            &format_timestamp(SystemTime::now() + retry_after, TimestampStyle::Relative),
+          ),
+        ],
+      ),
+      None => localize_with(
+        &locales.user,
+        "msg::roles::select:failed",
+        &[("roles", &roles)],
+      ),
+    });
+  }
+
+  // The member can keep adjusting their roles in the same message, with the menu now reflecting
+  // what they picked.
+  let selected_roles = selected_roles.into_iter().collect::<Vec<_>>();
+  let select = roles::menu(
+    &state,
+    guild_id,
+    self_roles,
+    source,
+    &selected_roles,
+    locales,
+  )
+  .await?;
 
   let response = InteractionResponseDataBuilder::new()
-    .flags(MessageFlags::EPHEMERAL)
-    .content(out)
+    .components([select])
+    .content(format!(
+      "{}\n\n{}",
+      localize(&locales.user, "msg::roles:content"),
+      status
+    ))
     .build();
 
   Ok(InteractionResponse {
     data: Some(response),
-    kind: InteractionResponseType::ChannelMessageWithSource,
+    kind: InteractionResponseType::UpdateMessage,
+  })
+}
+
+/// Selfroles to add and remove for a member to end up with the ones they picked.
+#[derive(Debug, PartialEq, Eq)]
+struct RoleChanges {
+  /// Selfroles the member had before picking.
+  previous_roles: HashSet<Id<RoleMarker>>,
+  added: Vec<Id<RoleMarker>>,
+  removed: Vec<Id<RoleMarker>>,
+}
+
+/// Compares the selfroles among `current_roles` with the picked `values`. Roles outside of the
+/// selfrole list are never touched, and only the selfroles that actually changed get a request, so
+/// other roles the member was given in the meantime are left alone.
+fn role_changes(
+  current_roles: &[Id<RoleMarker>],
+  self_roles: &[RoleData],
+  values: &[String],
+) -> anyhow::Result<RoleChanges> {
+  let self_role_ids = self_roles
+    .iter()
+    .map(|role| role.role_id)
+    .collect::<HashSet<_>>();
+
+  let selected_roles = values
+    .iter()
+    .map(|role| role.parse())
+    .collect::<Result<HashSet<_>, _>>()?
+    .intersection(&self_role_ids)
+    .cloned()
+    .collect::<HashSet<_>>();
+  let previous_roles = current_roles
+    .iter()
+    .filter(|role| self_role_ids.contains(role))
+    .cloned()
+    .collect::<HashSet<_>>();
+
+  let mut added = selected_roles
+    .difference(&previous_roles)
+    .cloned()
+    .collect::<Vec<_>>();
+  let mut removed = previous_roles
+    .difference(&selected_roles)
+    .cloned()
+    .collect::<Vec<_>>();
+  added.sort_unstable();
+  removed.sort_unstable();
+
+  Ok(RoleChanges {
+    previous_roles,
+    added,
+    removed,
   })
 }
+
+/// How long Discord asked to wait before trying again, when `error` is a rate limit.
+fn rate_limit(error: &anyhow::Error) -> Option<Duration> {
+  match error.downcast_ref::<twilight_http::Error>()?.kind() {
+    ErrorType::Response {
+      error: ApiError::Ratelimited(ratelimited),
+      ..
+    } => Duration::try_from_secs_f64(ratelimited.retry_after).ok(),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn self_roles(ids: &[u64]) -> Vec<RoleData> {
+    ids
+      .iter()
+      .map(|&id| RoleData {
+        role_id: Id::new(id),
+        label: id.to_string(),
+        description: None,
+      })
+      .collect()
+  }
+
+  fn ids(ids: &[u64]) -> Vec<Id<RoleMarker>> {
+    ids.iter().map(|&id| Id::new(id)).collect()
+  }
+
+  fn values(ids: &[u64]) -> Vec<String> {
+    ids.iter().map(u64::to_string).collect()
+  }
+
+  #[test]
+  fn only_changed_selfroles_are_touched() {
+    let changes = role_changes(
+      &ids(&[1, 2, 50]),
+      &self_roles(&[1, 2, 3, 4]),
+      &values(&[2, 3]),
+    )
+    .unwrap();
+
+    assert_eq!(changes.added, ids(&[3]));
+    assert_eq!(changes.removed, ids(&[1]));
+    assert_eq!(changes.previous_roles, ids(&[1, 2]).into_iter().collect());
+  }
+
+  #[test]
+  fn values_that_arent_selfroles_are_ignored() {
+    // A role removed from the list after the menu was opened, or a forged value.
+    let changes = role_changes(&ids(&[]), &self_roles(&[1]), &values(&[1, 60])).unwrap();
+    assert_eq!(changes.added, ids(&[1]));
+    assert!(changes.removed.is_empty());
+
+    assert!(role_changes(&ids(&[]), &self_roles(&[1]), &["everyone".to_string()]).is_err());
+  }
+
+  #[test]
+  fn roles_granted_in_the_meantime_are_left_alone() {
+    // A moderator gave the member role 50 and selfrole 2 after the menu was opened. Role 50 isn't
+    // a selfrole and 2 is still picked, so neither gets a request.
+    let changes = role_changes(&ids(&[2, 50]), &self_roles(&[1, 2]), &values(&[1, 2])).unwrap();
+    assert_eq!(changes.added, ids(&[1]));
+    assert!(changes.removed.is_empty());
+  }
+}
@@ -1,69 +1,242 @@
-use std::fmt::Display;
+use std::{fmt::Display, time::Instant};
+use tokio::sync::oneshot;
 use twilight_http::{api_error::ApiError, error::ErrorType};
 use twilight_model::{
-  application::interaction::InteractionData,
+  application::interaction::{InteractionData, InteractionType},
   channel::message::MessageFlags,
-  gateway::payload::incoming::InteractionCreate,
+  gateway::payload::incoming::{InteractionCreate, RoleDelete},
   http::interaction::{InteractionResponse, InteractionResponseType},
 };
 use twilight_util::builder::InteractionResponseDataBuilder;
 
-use crate::{commands, State};
+use crate::{
+  commands,
+  util::i18n::{localize, localize_with, Locales},
+  State,
+};
+
+fn format_error(locale: &str, error: impl Display) -> String {
+  localize_with(locale, "msg::error:unexpected", &[("error", &error)])
+}
+
+fn error_message(locale: &str, err: &anyhow::Error) -> String {
+  if let Some(e) = err.downcast_ref::<twilight_http::Error>() {
+    match e.kind() {
+      ErrorType::Response {
+        error: ApiError::General(error),
+        ..
+      } => format_error(locale, &error.message),
+      _ => format_error(locale, e),
+    }
+  } else if let Some(e) = err.downcast_ref::<sqlx::Error>() {
+    format_error(locale, e)
+  } else {
+    err.to_string()
+  }
+}
+
+/// Where the first response to an interaction goes. Everything after it, like editing in the result
+/// of a deferred handler, goes through the API either way.
+#[derive(Debug)]
+pub enum Responder {
+  /// The interaction callback endpoint, for interactions received over the gateway.
+  Rest,
+  /// The body of the pending webhook request, for interactions received over HTTP.
+  Http(oneshot::Sender<InteractionResponse>),
+}
+
+impl Responder {
+  async fn send(
+    self,
+    state: &State,
+    interaction: &InteractionCreate,
+    response: &InteractionResponse,
+  ) -> anyhow::Result<()> {
+    match self {
+      Responder::Rest => {
+        state
+          .client
+          .interaction(state.app_id)
+          .create_response(interaction.id, &interaction.token, response)
+          .exec()
+          .await?;
+      }
+      Responder::Http(sender) => sender
+        .send(response.clone())
+        .map_err(|_| anyhow::anyhow!("Interaction request closed before it got a response"))?,
+    }
 
-fn format_error(error: impl Display) -> String {
-  format!(
-    "Sorry, an unexpected error occured: {}\nPlease contact an administrator about this.",
-    error
-  )
+    Ok(())
+  }
+}
+
+/// Label for the kind of error in metrics, telling the same errors apart as `error_message`. Failed
+/// checks in the handlers are the localized message shown to the member, so their error is a
+/// `String`.
+fn error_kind(err: &anyhow::Error) -> &'static str {
+  if err.downcast_ref::<twilight_http::Error>().is_some() {
+    "twilight_http"
+  } else if err.downcast_ref::<sqlx::Error>().is_some() {
+    "sqlx"
+  } else if err.downcast_ref::<String>().is_some() {
+    "validation"
+  } else {
+    "other"
+  }
 }
 
 #[tracing::instrument(ret, skip_all)]
 pub async fn interaction_dispatcher(
   state: State,
   interaction: Box<InteractionCreate>,
+  responder: Responder,
+) -> anyhow::Result<()> {
+  let started = Instant::now();
+  let mut handler_error = None;
+  let result = respond(&state, &interaction, responder, &mut handler_error).await;
+
+  // A failed handler is still answered, with the error, so it's counted even when answering works.
+  let error_kind = handler_error.or_else(|| result.as_ref().err().map(error_kind));
+  state
+    .metrics
+    .interaction(&interaction, started.elapsed(), error_kind);
+
+  result
+}
+
+/// Runs the handler for `interaction` and answers it. The kind of error the handler failed with, if
+/// any, goes into `handler_error`.
+async fn respond(
+  state: &State,
+  interaction: &InteractionCreate,
+  responder: Responder,
+  handler_error: &mut Option<&'static str>,
 ) -> anyhow::Result<()> {
-  let response = match interaction.data {
-    Some(InteractionData::ApplicationCommand(ref command)) => {
-      commands::handle_command(state.clone(), command, &interaction).await
+  let client = state.client.interaction(state.app_id);
+
+  // Slow handlers get a loading state right away, and their result is edited in afterwards. This
+  // goes out before anything else, including looking up the guild's language in the store.
+  let deferred = commands::deferred_response(interaction);
+  let responder = match &deferred {
+    Some(deferred) => {
+      responder.send(state, interaction, deferred).await?;
+      None
+    }
+    None => Some(responder),
+  };
+
+  let locales = Locales::for_interaction(state.store.as_ref(), interaction).await;
+
+  let result = match interaction.data {
+    // Autocomplete carries command data too, but the commands don't have options to complete.
+    Some(InteractionData::ApplicationCommand(ref command))
+      if interaction.kind == InteractionType::ApplicationCommand =>
+    {
+      commands::handle_command(state.clone(), command, interaction, &locales).await
     }
     Some(InteractionData::MessageComponent(ref component)) => {
-      commands::handle_menu(state.clone(), interaction.clone(), component).await
+      let interaction = Box::new(interaction.clone());
+      commands::handle_menu(state.clone(), interaction, component, &locales).await
     }
-    _ => unreachable!(),
-  }
-  .unwrap_or_else(|err| {
-    let err_message = if let Some(e) = err.downcast_ref::<twilight_http::Error>() {
-      match e.kind() {
-        ErrorType::Response {
-          error: ApiError::General(error),
-          ..
-        } => format_error(&error.message),
-        _ => format_error(e),
-      }
-    } else if let Some(e) = err.downcast_ref::<sqlx::Error>() {
-      format_error(e)
-    } else {
-      err.to_string()
-    };
-
-    InteractionResponse {
-      kind: InteractionResponseType::ChannelMessageWithSource,
-      data: Some(
-        InteractionResponseDataBuilder::new()
-          .content(err_message)
+    // Nothing sends modals either, but any signed request can claim to be one.
+    _ => Err(anyhow::anyhow!(localize(
+      &locales.user,
+      "msg::error:unsupported"
+    ))),
+  };
+  *handler_error = result.as_ref().err().map(error_kind);
+
+  let deferred = match (deferred, responder) {
+    (Some(deferred), _) => deferred,
+    (None, Some(responder)) => {
+      let response = result.unwrap_or_else(|err| InteractionResponse {
+        kind: InteractionResponseType::ChannelMessageWithSource,
+        data: Some(
+          InteractionResponseDataBuilder::new()
+            .content(error_message(&locales.user, &err))
+            .flags(MessageFlags::EPHEMERAL)
+            .build(),
+        ),
+      });
+
+      responder.send(state, interaction, &response).await?;
+
+      return Ok(());
+    }
+    (None, None) => unreachable!(),
+  };
+
+  match result {
+    Ok(InteractionResponse {
+      data: Some(data), ..
+    }) => {
+      client
+        .update_response(&interaction.token)
+        .content(data.content.as_deref())?
+        .components(data.components.as_deref())?
+        .embeds(data.embeds.as_deref())?
+        .exec()
+        .await?;
+    }
+    Ok(_) => (),
+    Err(err) => {
+      let ephemeral = deferred
+        .data
+        .and_then(|data| data.flags)
+        .is_some_and(|flags| flags.contains(MessageFlags::EPHEMERAL));
+
+      // Public messages can't be turned ephemeral, so errors go into a followup only the invoking
+      // member can see. A loading message of our own is removed first, an updated one is left as is.
+      if ephemeral {
+        client
+          .update_response(&interaction.token)
+          .content(Some(error_message(&locales.user, &err).as_str()))?
+          .exec()
+          .await?;
+      } else {
+        if deferred.kind == InteractionResponseType::DeferredChannelMessageWithSource {
+          client.delete_response(&interaction.token).exec().await?;
+        }
+        client
+          .create_followup(&interaction.token)
+          .content(&error_message(&locales.user, &err))?
           .flags(MessageFlags::EPHEMERAL)
-          .build(),
-      ),
+          .exec()
+          .await?;
+      }
     }
-  });
+  }
 
-  let client = state.client.interaction(state.app_id);
-  client
-    .create_response(interaction.id, &interaction.token, &response)
-    .exec()
-    .await?
-    .text()
-    .await?;
+  Ok(())
+}
+
+/// A deleted role can't be given out anymore, so it stops being a selfrole. This also drops the
+/// cached selfroles of the guild.
+#[tracing::instrument(ret, skip_all)]
+pub async fn role_deleted(state: State, delete: RoleDelete) -> anyhow::Result<()> {
+  if state.store.remove(delete.guild_id, delete.role_id).await? {
+    tracing::info!(
+      "Role {} was deleted from guild {}, removed it from the selfroles",
+      delete.role_id,
+      delete.guild_id
+    );
+  }
 
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn error_kinds() {
+    let failed_check = (|| {
+      anyhow::ensure!(false, "Role is above the bot".to_string());
+      Ok(())
+    })();
+    assert_eq!(error_kind(&failed_check.unwrap_err()), "validation");
+    assert_eq!(error_kind(&sqlx::Error::RowNotFound.into()), "sqlx");
+    assert_eq!(error_kind(&anyhow::anyhow!("Request closed")), "other");
+  }
+}
@@ -0,0 +1,163 @@
+use std::{
+  collections::BTreeMap,
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex,
+  },
+  time::{Duration, Instant},
+};
+
+use crate::util::store::SelfRoleStore;
+
+/// How often the webhook server reports that the runtime is still going, as it has no event loop.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long the event loop can go without reporting before it's considered stuck. The gateway
+/// reports once per event it handles, and even an idle bot gets an acknowledgement of each shard's
+/// heartbeat, which Discord asks for about every 41 seconds.
+const STUCK_AFTER: Duration = Duration::from_secs(90);
+/// How long the store gets to answer a readiness check.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// What `/healthz` and `/readyz` report on, updated as the bot starts up and runs.
+#[derive(Debug)]
+pub struct Health {
+  app_id: AtomicBool,
+  /// Set while the bot is going to run shards but doesn't know which ones yet.
+  gateway_pending: AtomicBool,
+  /// Shards this process runs, and whether each is connected.
+  shards: Mutex<BTreeMap<u64, bool>>,
+  heartbeat: Mutex<Instant>,
+}
+
+impl Default for Health {
+  fn default() -> Self {
+    Self {
+      app_id: AtomicBool::new(false),
+      gateway_pending: AtomicBool::new(false),
+      shards: Mutex::default(),
+      heartbeat: Mutex::new(Instant::now()),
+    }
+  }
+}
+
+impl Health {
+  pub fn app_id_resolved(&self) {
+    self.app_id.store(true, Ordering::SeqCst);
+  }
+
+  /// Marks the bot as running on the gateway, so it isn't ready before the cluster is built and
+  /// its shards are known.
+  pub fn expect_gateway(&self) {
+    self.gateway_pending.store(true, Ordering::SeqCst);
+  }
+
+  /// Starts tracking the shards of the cluster, none of which are connected yet.
+  pub fn expect_shards(&self, shard_ids: impl IntoIterator<Item = u64>) {
+    let mut shards = self.shards.lock().unwrap();
+    shards.extend(shard_ids.into_iter().map(|shard_id| (shard_id, false)));
+    self.gateway_pending.store(false, Ordering::SeqCst);
+  }
+
+  pub fn shard_connected(&self, shard_id: u64, connected: bool) {
+    self.shards.lock().unwrap().insert(shard_id, connected);
+  }
+
+  pub fn beat(&self) {
+    *self.heartbeat.lock().unwrap() = Instant::now();
+  }
+
+  /// Whether the process should be left running, which it shouldn't when the event loop is stuck.
+  /// Over HTTP this only catches a starved runtime, since there are no events when nobody uses
+  /// the bot.
+  pub fn live(&self) -> Result<(), String> {
+    let since = self.heartbeat.lock().unwrap().elapsed();
+    if since > STUCK_AFTER {
+      return Err(format!("No heartbeat from the event loop for {since:?}"));
+    }
+
+    Ok(())
+  }
+
+  /// Whether the bot can handle interactions: it knows its application id, every shard it runs is
+  /// connected and the store responds.
+  pub async fn ready(&self, store: &dyn SelfRoleStore) -> Result<(), String> {
+    if !self.app_id.load(Ordering::SeqCst) {
+      return Err("Application id not resolved yet".to_string());
+    }
+    if self.gateway_pending.load(Ordering::SeqCst) {
+      return Err("Gateway cluster not started yet".to_string());
+    }
+
+    let disconnected = self
+      .shards
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|(_, connected)| !**connected)
+      .map(|(shard_id, _)| shard_id.to_string())
+      .collect::<Vec<_>>();
+    if !disconnected.is_empty() {
+      return Err(format!("Shards not connected: {}", disconnected.join(", ")));
+    }
+
+    match tokio::time::timeout(PING_TIMEOUT, store.ping()).await {
+      Ok(Ok(())) => Ok(()),
+      Ok(Err(error)) => Err(format!("Store not responding: {error}")),
+      Err(_) => Err(format!("Store not responding after {PING_TIMEOUT:?}")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::store::InMemoryStore;
+
+  #[tokio::test]
+  async fn ready_once_started_and_connected() {
+    let health = Health::default();
+    let store = InMemoryStore::default();
+
+    assert!(health.ready(&store).await.is_err());
+    health.app_id_resolved();
+    health.expect_shards([0, 1]);
+    health.shard_connected(0, true);
+    assert_eq!(
+      health.ready(&store).await,
+      Err("Shards not connected: 1".to_string())
+    );
+
+    health.shard_connected(1, true);
+    assert_eq!(health.ready(&store).await, Ok(()));
+  }
+
+  #[tokio::test]
+  async fn not_ready_before_the_shards_are_known() {
+    let health = Health::default();
+    let store = InMemoryStore::default();
+
+    health.expect_gateway();
+    health.app_id_resolved();
+    assert_eq!(
+      health.ready(&store).await,
+      Err("Gateway cluster not started yet".to_string())
+    );
+
+    health.expect_shards([0]);
+    assert!(health.ready(&store).await.is_err());
+    health.shard_connected(0, true);
+    assert_eq!(health.ready(&store).await, Ok(()));
+  }
+
+  #[test]
+  fn not_live_without_heartbeat() {
+    let health = Health::default();
+    assert!(health.live().is_ok());
+
+    *health.heartbeat.lock().unwrap() = Instant::now() - STUCK_AFTER * 2;
+    assert!(health.live().is_err());
+
+    health.beat();
+    assert!(health.live().is_ok());
+  }
+}
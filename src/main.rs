@@ -1,26 +1,76 @@
+use events::Responder;
 use futures::StreamExt;
-use sqlx::SqlitePool;
+use health::Health;
+use metrics::Metrics;
+use ops::Ops;
 use std::{env, sync::Arc};
+use twilight_cache_inmemory::InMemoryCache;
 use twilight_gateway::{Cluster, Event, Intents};
 use twilight_http::{client::ClientBuilder, Client};
 use twilight_model::{
   channel::message::AllowedMentions,
-  id::{marker::ApplicationMarker, Id},
+  id::{
+    marker::{ApplicationMarker, RoleMarker},
+    Id,
+  },
+};
+use util::{
+  database::SqlStore,
+  log_channel::LogChannel,
+  shutdown::Tasks,
+  store::{CachedStore, InMemoryStore, SelfRoleStore},
 };
 
 pub mod commands;
 pub mod events;
+pub mod health;
+pub mod metrics;
+pub mod ops;
 pub mod util;
+pub mod webhook;
 
 #[derive(Debug, Clone)]
 pub struct State {
-  pool: SqlitePool,
+  store: Arc<dyn SelfRoleStore>,
   client: Arc<Client>,
+  cache: Arc<InMemoryCache>,
   app_id: Id<ApplicationMarker>,
+  log_channel: LogChannel,
+  tasks: Tasks,
+  metrics: Arc<Metrics>,
+  health: Arc<Health>,
 }
 
+#[cfg(test)]
+impl State {
+  /// State for handler tests, backed by `store` and a client that never gets to make a request.
+  fn with_store(store: Arc<dyn SelfRoleStore>) -> Self {
+    let client = Arc::new(Client::new(String::new()));
+
+    Self {
+      log_channel: LogChannel::spawn(Arc::clone(&store), Arc::clone(&client)),
+      store,
+      app_id: Id::new(1),
+      client,
+      cache: Arc::new(util::cache::new()),
+      tasks: Tasks::default(),
+      metrics: Arc::default(),
+      health: Arc::default(),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
 pub struct RoleData {
-  role_id: String,
+  role_id: Id<RoleMarker>,
+  label: String,
+  description: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoleLabel {
+  role_id: Id<RoleMarker>,
+  locale: String,
   label: String,
   description: Option<String>,
 }
@@ -33,47 +83,154 @@ async fn main() -> anyhow::Result<()> {
 
   tracing_subscriber::fmt::init();
 
-  let (cluster, mut events) = Cluster::new(env::var("TOKEN")?, Intents::GUILDS).await?;
-  let cluster = Arc::new(cluster);
-
-  let cluster_spawn = Arc::clone(&cluster);
-
-  tokio::spawn(async move {
-    cluster_spawn.up().await;
-  });
-
   let client: Arc<_> = ClientBuilder::new()
     .default_allowed_mentions(AllowedMentions::default())
     .token(env::var("TOKEN")?)
     .build()
     .into();
 
+  let store: Arc<dyn SelfRoleStore> = match env::var("DATABASE_URL")?.as_str() {
+    "memory" => Arc::new(InMemoryStore::default()),
+    url => Arc::new(CachedStore::new(Arc::new(SqlStore::new(
+      util::database::connect(url).await?,
+    )))),
+  };
+
+  let cache = Arc::new(util::cache::new());
+  let metrics = Arc::<Metrics>::default();
+  let health = Arc::<Health>::default();
+  let interactions_addr = env::var("INTERACTIONS_ADDR");
+  if interactions_addr.is_err() {
+    health.expect_gateway();
+  }
+
+  // Started before the application id is resolved, so the bot shows up as alive but not ready.
+  if let Ok(addr) = env::var("OPS_ADDR") {
+    let addr = addr.parse()?;
+    let ops = Ops {
+      store: Arc::clone(&store),
+      cache: Arc::clone(&cache),
+      metrics: Arc::clone(&metrics),
+      health: Arc::clone(&health),
+    };
+    tokio::spawn(async move {
+      if let Err(error) = ops::serve(ops, addr).await {
+        tracing::error!("{}", error);
+      }
+    });
+  }
+
+  let app_id = client
+    .current_user_application()
+    .exec()
+    .await?
+    .model()
+    .await?
+    .id;
+  health.app_id_resolved();
+
   let state = State {
-    pool: SqlitePool::connect("sqlite:db.db").await?,
-    app_id: client
-      .current_user_application()
-      .exec()
-      .await?
-      .model()
-      .await?
-      .id,
+    log_channel: LogChannel::spawn(Arc::clone(&store), Arc::clone(&client)),
+    store,
+    app_id,
     client,
+    cache,
+    tasks: Tasks::default(),
+    metrics,
+    health,
+  };
+
+  // With an interactions endpoint set up in the developer portal, Discord posts interactions to it
+  // and the gateway isn't needed.
+  let result = match interactions_addr {
+    Ok(addr) => {
+      let public_key = webhook::public_key(&env::var("PUBLIC_KEY")?)?;
+      webhook::serve(state.clone(), addr.parse()?, public_key).await
+    }
+    Err(_) => gateway(state.clone()).await,
   };
 
-  while let Some((id, event)) = events.next().await {
+  // Interactions still in progress were waited for, so the last log lines are in by now.
+  state.log_channel.close().await;
+  state.store.close().await;
+
+  result
+}
+
+async fn gateway(state: State) -> anyhow::Result<()> {
+  let mut builder = Cluster::builder(env::var("TOKEN")?, Intents::GUILDS)
+    .resume_sessions(util::shards::load_sessions());
+  if let Some(scheme) = util::shards::scheme()? {
+    builder = builder.shard_scheme(scheme);
+  }
+  let (cluster, mut events) = builder.build().await?;
+  let cluster = Arc::new(cluster);
+  state
+    .health
+    .expect_shards(cluster.shards().map(|shard| shard.config().shard()[0]));
+
+  let cluster_spawn = Arc::clone(&cluster);
+
+  tokio::spawn(async move {
+    cluster_spawn.up().await;
+  });
+
+  let shutdown = util::shutdown::signal();
+  tokio::pin!(shutdown);
+
+  loop {
+    let (id, event) = tokio::select! {
+      _ = &mut shutdown => break,
+      next = events.next() => match next {
+        Some(next) => next,
+        None => break,
+      },
+    };
     // println!("Shard: {id}, Event: {:?}", event.kind());
+    state.cache.update(&event);
+
     match event {
       Event::InteractionCreate(interaction) => {
-        let state = state.clone();
-        tokio::spawn(async move {
-          if let Err(error) = events::interaction_dispatcher(state, interaction).await {
+        let state_spawn = state.clone();
+        state.tasks.spawn(async move {
+          if let Err(error) =
+            events::interaction_dispatcher(state_spawn, interaction, Responder::Rest).await
+          {
             tracing::error!("{}", error);
           }
         });
       }
+      Event::RoleDelete(delete) => {
+        let state_spawn = state.clone();
+        state.tasks.spawn(async move {
+          if let Err(error) = events::role_deleted(state_spawn, delete).await {
+            tracing::error!("{}", error);
+          }
+        });
+      }
+      Event::ShardConnected(_) => {
+        state.health.shard_connected(id, true);
+        state.metrics.shard_connected(id, true);
+      }
+      Event::ShardDisconnected(_) | Event::ShardReconnecting(_) => {
+        state.health.shard_connected(id, false);
+        state.metrics.shard_connected(id, false);
+      }
       _ => tracing::debug!("Shard: {id}, Event: {:?}", event.kind()),
     }
+
+    // Only events that made it through the loop count, so liveness fails when they stop coming in
+    // or stop being handled. Heartbeat acknowledgements keep it up when the bot is idle.
+    state.health.beat();
   }
 
+  // Disconnect first, so events the loop no longer reads aren't acknowledged by the saved sessions
+  // and get replayed when they're resumed.
+  let sessions = cluster.down_resumable();
+  // The ones being handled still get to finish, their responses go over REST.
+  state.tasks.drain().await;
+  // Saved so a quick restart resumes the sessions instead of starting new ones.
+  util::shards::save_sessions(&sessions);
+
   Ok(())
 }
@@ -0,0 +1,159 @@
+use prometheus::{
+  Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+  TextEncoder,
+};
+use std::{fmt, time::Duration};
+use twilight_cache_inmemory::InMemoryCache;
+use twilight_model::{
+  application::interaction::{application_command::CommandOptionValue, InteractionData},
+  gateway::payload::incoming::InteractionCreate,
+};
+
+const NAMESPACE: &str = "roles";
+
+/// Counters for what the bot has been doing, rendered for Prometheus by `/metrics`.
+pub struct Metrics {
+  registry: Registry,
+  interactions: IntCounterVec,
+  latency: HistogramVec,
+  errors: IntCounterVec,
+  guilds: IntGauge,
+  shards: IntGaugeVec,
+}
+
+impl fmt::Debug for Metrics {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Metrics").finish_non_exhaustive()
+  }
+}
+
+impl Default for Metrics {
+  fn default() -> Self {
+    let interactions = IntCounterVec::new(
+      Opts::new(
+        "interactions_total",
+        "Interactions handled, by command or component custom id",
+      )
+      .namespace(NAMESPACE),
+      &["kind", "name"],
+    )
+    .unwrap();
+    let latency = HistogramVec::new(
+      HistogramOpts::new(
+        "interaction_duration_seconds",
+        "Time spent handling interactions, by command or component custom id",
+      )
+      .namespace(NAMESPACE),
+      &["kind", "name"],
+    )
+    .unwrap();
+    let errors = IntCounterVec::new(
+      Opts::new(
+        "interaction_errors_total",
+        "Failed interactions, by error kind",
+      )
+      .namespace(NAMESPACE),
+      &["kind"],
+    )
+    .unwrap();
+    let guilds = IntGauge::with_opts(
+      Opts::new("guilds", "Guilds received over the gateway").namespace(NAMESPACE),
+    )
+    .unwrap();
+    let shards = IntGaugeVec::new(
+      Opts::new("shard_connected", "Whether each gateway shard is connected").namespace(NAMESPACE),
+      &["shard"],
+    )
+    .unwrap();
+
+    let registry = Registry::new();
+    registry.register(Box::new(interactions.clone())).unwrap();
+    registry.register(Box::new(latency.clone())).unwrap();
+    registry.register(Box::new(errors.clone())).unwrap();
+    registry.register(Box::new(guilds.clone())).unwrap();
+    registry.register(Box::new(shards.clone())).unwrap();
+
+    Self {
+      registry,
+      interactions,
+      latency,
+      errors,
+      guilds,
+      shards,
+    }
+  }
+}
+
+impl Metrics {
+  /// Records a handled interaction. `error_kind` is set for the ones that failed.
+  pub fn interaction(
+    &self,
+    interaction: &InteractionCreate,
+    duration: Duration,
+    error_kind: Option<&str>,
+  ) {
+    let (kind, name) = match &interaction.data {
+      Some(InteractionData::ApplicationCommand(command)) => {
+        // Subcommands count on their own, as `/config add` and `/config remove` have little in
+        // common.
+        let name = match command.options.first() {
+          Some(option) if matches!(option.value, CommandOptionValue::SubCommand(_)) => {
+            format!("{} {}", command.name, option.name)
+          }
+          _ => command.name.clone(),
+        };
+        ("command", name)
+      }
+      Some(InteractionData::MessageComponent(component)) => {
+        ("component", component.custom_id.clone())
+      }
+      _ => ("other", String::new()),
+    };
+
+    self.interactions.with_label_values(&[kind, &name]).inc();
+    self
+      .latency
+      .with_label_values(&[kind, &name])
+      .observe(duration.as_secs_f64());
+    if let Some(error_kind) = error_kind {
+      self.errors.with_label_values(&[error_kind]).inc();
+    }
+  }
+
+  pub fn shard_connected(&self, shard_id: u64, connected: bool) {
+    self
+      .shards
+      .with_label_values(&[&shard_id.to_string()])
+      .set(connected.into());
+  }
+
+  /// Metrics in the Prometheus text format.
+  pub fn render(&self, cache: &InMemoryCache) -> anyhow::Result<(String, Vec<u8>)> {
+    self.guilds.set(cache.stats().guilds().try_into()?);
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder.encode(&self.registry.gather(), &mut buffer)?;
+
+    Ok((encoder.format_type().to_string(), buffer))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn renders_recorded_metrics() {
+    let metrics = Metrics::default();
+    metrics.shard_connected(0, true);
+    metrics.errors.with_label_values(&["sqlx"]).inc();
+
+    let (content_type, body) = metrics.render(&InMemoryCache::new()).unwrap();
+    let body = String::from_utf8(body).unwrap();
+    assert!(content_type.starts_with("text/plain"));
+    assert!(body.contains("roles_shard_connected{shard=\"0\"} 1"));
+    assert!(body.contains("roles_interaction_errors_total{kind=\"sqlx\"} 1"));
+    assert!(body.contains("roles_guilds 0"));
+  }
+}
@@ -0,0 +1,65 @@
+use hyper::{
+  header::CONTENT_TYPE,
+  service::{make_service_fn, service_fn},
+  Body, Method, Request, Response, Server, StatusCode,
+};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use twilight_cache_inmemory::InMemoryCache;
+
+use crate::{health::Health, metrics::Metrics, util::store::SelfRoleStore};
+
+/// What the monitoring endpoints look at. They're served before the rest of `State` is ready, so
+/// the bot can report that it's still starting.
+#[derive(Debug, Clone)]
+pub struct Ops {
+  pub store: Arc<dyn SelfRoleStore>,
+  pub cache: Arc<InMemoryCache>,
+  pub metrics: Arc<Metrics>,
+  pub health: Arc<Health>,
+}
+
+/// Serves the endpoints for monitoring the bot on `addr`: `/metrics` for Prometheus, and `/healthz`
+/// and `/readyz` for liveness and readiness checks.
+pub async fn serve(ops: Ops, addr: SocketAddr) -> anyhow::Result<()> {
+  let make_service = make_service_fn(move |_| {
+    let ops = ops.clone();
+    async move { Ok::<_, Infallible>(service_fn(move |request| handle(ops.clone(), request))) }
+  });
+
+  tracing::info!("Serving metrics and health checks on {addr}");
+  Server::bind(&addr).serve(make_service).await?;
+
+  Ok(())
+}
+
+async fn handle(ops: Ops, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+  let response = match (request.method(), request.uri().path()) {
+    (&Method::GET, "/metrics") => match ops.metrics.render(&ops.cache) {
+      Ok((content_type, body)) => Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .body(body.into()),
+      Err(error) => {
+        tracing::error!("Failed to render metrics: {}", error);
+        Response::builder()
+          .status(StatusCode::INTERNAL_SERVER_ERROR)
+          .body(Body::empty())
+      }
+    },
+    (&Method::GET, "/healthz") => check(ops.health.live()),
+    (&Method::GET, "/readyz") => check(ops.health.ready(ops.store.as_ref()).await),
+    _ => Response::builder()
+      .status(StatusCode::NOT_FOUND)
+      .body(Body::empty()),
+  };
+
+  Ok(response.unwrap())
+}
+
+fn check(result: Result<(), String>) -> hyper::http::Result<Response<Body>> {
+  match result {
+    Ok(()) => Response::builder().body("ok".into()),
+    Err(reason) => Response::builder()
+      .status(StatusCode::SERVICE_UNAVAILABLE)
+      .body(reason.into()),
+  }
+}
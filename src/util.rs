@@ -1 +1,8 @@
+pub mod cache;
+pub mod change_source;
+pub mod database;
 pub mod i18n;
+pub mod log_channel;
+pub mod shards;
+pub mod shutdown;
+pub mod store;
@@ -0,0 +1,72 @@
+use twilight_cache_inmemory::{InMemoryCache, ResourceType};
+use twilight_model::{
+  guild::Role,
+  id::{
+    marker::{GuildMarker, RoleMarker},
+    Id,
+  },
+};
+
+use crate::State;
+
+/// Cache fed from gateway events. With only the `GUILDS` intent, the member list sent in
+/// `GUILD_CREATE` holds just the bot itself, which is the one member we need.
+pub fn new() -> InMemoryCache {
+  InMemoryCache::builder()
+    .resource_types(ResourceType::GUILD | ResourceType::ROLE | ResourceType::MEMBER)
+    .build()
+}
+
+/// Roles of the guild, from the cache once the guild has arrived over the gateway and from the
+/// API before that.
+pub async fn guild_roles(state: &State, guild_id: Id<GuildMarker>) -> anyhow::Result<Vec<Role>> {
+  if let Some(roles) = cached_guild_roles(state, guild_id) {
+    return Ok(roles);
+  }
+
+  tracing::debug!("Roles of guild {guild_id} not cached, fetching them");
+  Ok(state.client.roles(guild_id).exec().await?.model().await?)
+}
+
+fn cached_guild_roles(state: &State, guild_id: Id<GuildMarker>) -> Option<Vec<Role>> {
+  let role_ids = state.cache.guild_roles(guild_id)?;
+
+  role_ids
+    .iter()
+    .map(|role_id| {
+      state
+        .cache
+        .role(*role_id)
+        .map(|role| role.resource().clone())
+    })
+    .collect()
+}
+
+/// Roles of the bot in the guild. Updates to the bot member are only sent with the `GUILD_MEMBERS`
+/// intent, so the cached roles may be out of date; `fresh` skips the cache when that matters.
+pub async fn bot_roles(
+  state: &State,
+  guild_id: Id<GuildMarker>,
+  fresh: bool,
+) -> anyhow::Result<Vec<Id<RoleMarker>>> {
+  if !fresh {
+    let cached = state
+      .cache
+      .member(guild_id, state.app_id.cast())
+      .map(|member| member.roles().to_vec());
+
+    if let Some(roles) = cached {
+      return Ok(roles);
+    }
+  }
+
+  let member = state
+    .client
+    .guild_member(guild_id, state.app_id.cast())
+    .exec()
+    .await?
+    .model()
+    .await?;
+
+  Ok(member.roles)
+}
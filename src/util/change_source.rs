@@ -0,0 +1,39 @@
+use crate::commands::manifest::components;
+
+/// Where a selfrole change came from. Each source uses its own select menu custom id, so the
+/// origin survives the round trip through Discord and ends up in the audit log reason.
+///
+/// Only the menus exist so far. Reaction roles, expiring roles, roles granted on a moderator's
+/// approval and autoroles each get a variant with their own reason when they're added, an approval
+/// one naming the moderator, rather than reasons for changes the bot can't make yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeSource {
+  /// Menu opened with the `/roles` command.
+  Menu,
+  /// Menu opened with a button created by `/persist`.
+  Panel,
+}
+
+impl ChangeSource {
+  pub fn menu_id(self) -> &'static str {
+    match self {
+      ChangeSource::Menu => components::ROLE_MENU,
+      ChangeSource::Panel => components::PANEL_ROLE_MENU,
+    }
+  }
+
+  pub fn from_menu_id(custom_id: &str) -> Option<Self> {
+    match custom_id {
+      components::ROLE_MENU => Some(ChangeSource::Menu),
+      components::PANEL_ROLE_MENU => Some(ChangeSource::Panel),
+      _ => None,
+    }
+  }
+
+  pub fn reason(self) -> &'static str {
+    match self {
+      ChangeSource::Menu => "Self role: select menu (/roles)",
+      ChangeSource::Panel => "Self role: button panel (/persist)",
+    }
+  }
+}
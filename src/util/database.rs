@@ -0,0 +1,281 @@
+use async_trait::async_trait;
+use sqlx::{
+  any::{AnyConnectOptions, AnyKind, AnyPoolOptions, AnyRow},
+  migrate::Migrator,
+  sqlite::SqliteConnectOptions,
+  AnyPool, FromRow, Row,
+};
+use std::str::FromStr;
+use twilight_model::id::{
+  marker::{ChannelMarker, GuildMarker, RoleMarker},
+  Id,
+};
+
+use crate::{util::store::SelfRoleStore, RoleData, RoleLabel};
+
+static SQLITE_MIGRATIONS: Migrator = sqlx::migrate!("migrations/sqlite");
+static POSTGRES_MIGRATIONS: Migrator = sqlx::migrate!("migrations/postgres");
+
+/// Connect to the SQLite or PostgreSQL database at `url`, depending on its scheme, and apply any
+/// migrations it's missing. A SQLite database file is created if it doesn't exist yet.
+pub async fn connect(url: &str) -> anyhow::Result<AnyPool> {
+  let kind = AnyKind::from_str(url)?;
+  let options = match kind {
+    AnyKind::Sqlite => SqliteConnectOptions::from_str(url)?
+      .create_if_missing(true)
+      .into(),
+    AnyKind::Postgres => AnyConnectOptions::from_str(url)?,
+  };
+
+  let pool = AnyPoolOptions::new().connect_with(options).await?;
+  let migrator = match kind {
+    AnyKind::Sqlite => &SQLITE_MIGRATIONS,
+    AnyKind::Postgres => &POSTGRES_MIGRATIONS,
+  };
+  migrator.run(&pool).await?;
+
+  Ok(pool)
+}
+
+/// `SelfRoleStore` backed by a SQLite or PostgreSQL database.
+#[derive(Debug, Clone)]
+pub struct SqlStore {
+  pool: AnyPool,
+}
+
+impl SqlStore {
+  pub fn new(pool: AnyPool) -> Self {
+    Self { pool }
+  }
+}
+
+/// Snowflakes are stored as signed 64-bit integers, which every backend supports. They won't
+/// reach the sign bit for a long time yet.
+fn snowflake<T>(id: Id<T>) -> i64 {
+  id.get() as i64
+}
+
+fn id<T>(value: i64) -> Option<Id<T>> {
+  Id::new_checked(value as u64)
+}
+
+fn get_id<T>(row: &AnyRow, column: &str) -> sqlx::Result<Id<T>> {
+  id(row.try_get(column)?).ok_or_else(|| sqlx::Error::ColumnDecode {
+    index: column.to_string(),
+    source: "snowflake can't be 0".into(),
+  })
+}
+
+impl FromRow<'_, AnyRow> for RoleData {
+  fn from_row(row: &AnyRow) -> sqlx::Result<Self> {
+    Ok(Self {
+      role_id: get_id(row, "role_id")?,
+      label: row.try_get("label")?,
+      description: row.try_get("description")?,
+    })
+  }
+}
+
+impl FromRow<'_, AnyRow> for RoleLabel {
+  fn from_row(row: &AnyRow) -> sqlx::Result<Self> {
+    Ok(Self {
+      role_id: get_id(row, "role_id")?,
+      locale: row.try_get("locale")?,
+      label: row.try_get("label")?,
+      description: row.try_get("description")?,
+    })
+  }
+}
+
+#[async_trait]
+impl SelfRoleStore for SqlStore {
+  async fn list_for_guild(&self, guild_id: Id<GuildMarker>) -> anyhow::Result<Vec<RoleData>> {
+    let roles = sqlx::query_as("SELECT role_id, label, description FROM roles WHERE guild_id = $1")
+      .bind(snowflake(guild_id))
+      .fetch_all(&self.pool)
+      .await?;
+
+    Ok(roles)
+  }
+
+  async fn exists(
+    &self,
+    guild_id: Id<GuildMarker>,
+    role_id: Id<RoleMarker>,
+  ) -> anyhow::Result<bool> {
+    let count: i64 =
+      sqlx::query_scalar("SELECT COUNT(*) FROM roles WHERE guild_id = $1 AND role_id = $2")
+        .bind(snowflake(guild_id))
+        .bind(snowflake(role_id))
+        .fetch_one(&self.pool)
+        .await?;
+
+    Ok(count > 0)
+  }
+
+  async fn upsert(
+    &self,
+    guild_id: Id<GuildMarker>,
+    role_id: Id<RoleMarker>,
+    label: &str,
+    description: Option<&str>,
+  ) -> anyhow::Result<()> {
+    sqlx::query(
+      r#"
+        INSERT INTO roles VALUES ($1, $2, $3, $4)
+        ON CONFLICT (role_id) DO UPDATE SET
+          label = excluded.label,
+          description = excluded.description
+        ;
+      "#,
+    )
+    .bind(snowflake(guild_id))
+    .bind(snowflake(role_id))
+    .bind(label)
+    .bind(description)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn remove(
+    &self,
+    guild_id: Id<GuildMarker>,
+    role_id: Id<RoleMarker>,
+  ) -> anyhow::Result<bool> {
+    let removed =
+      sqlx::query("DELETE FROM roles WHERE guild_id = $1 AND role_id = $2 RETURNING role_id")
+        .bind(snowflake(guild_id))
+        .bind(snowflake(role_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+    sqlx::query("DELETE FROM role_labels WHERE role_id = $1")
+      .bind(snowflake(role_id))
+      .execute(&self.pool)
+      .await?;
+
+    Ok(removed.is_some())
+  }
+
+  async fn labels_for_guild(&self, guild_id: Id<GuildMarker>) -> anyhow::Result<Vec<RoleLabel>> {
+    let labels = sqlx::query_as(
+      r#"
+        SELECT
+          role_labels.role_id,
+          role_labels.locale,
+          role_labels.label,
+          role_labels.description
+        FROM role_labels JOIN roles ON roles.role_id = role_labels.role_id
+        WHERE roles.guild_id = $1
+      "#,
+    )
+    .bind(snowflake(guild_id))
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(labels)
+  }
+
+  async fn upsert_label(
+    &self,
+    role_id: Id<RoleMarker>,
+    locale: &str,
+    label: &str,
+    description: Option<&str>,
+  ) -> anyhow::Result<()> {
+    sqlx::query(
+      r#"
+        INSERT INTO role_labels VALUES ($1, $2, $3, $4)
+        ON CONFLICT (role_id, locale) DO UPDATE SET
+          label = excluded.label,
+          description = excluded.description
+        ;
+      "#,
+    )
+    .bind(snowflake(role_id))
+    .bind(locale)
+    .bind(label)
+    .bind(description)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn log_channel(
+    &self,
+    guild_id: Id<GuildMarker>,
+  ) -> anyhow::Result<Option<Id<ChannelMarker>>> {
+    let log_channel: Option<Option<i64>> =
+      sqlx::query_scalar("SELECT log_channel FROM guilds WHERE guild_id = $1")
+        .bind(snowflake(guild_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+    Ok(log_channel.flatten().and_then(id))
+  }
+
+  async fn set_log_channel(
+    &self,
+    guild_id: Id<GuildMarker>,
+    channel_id: Option<Id<ChannelMarker>>,
+  ) -> anyhow::Result<()> {
+    sqlx::query(
+      r#"
+        INSERT INTO guilds (guild_id, log_channel) VALUES ($1, $2)
+        ON CONFLICT (guild_id) DO UPDATE SET
+          log_channel = excluded.log_channel
+        ;
+      "#,
+    )
+    .bind(snowflake(guild_id))
+    .bind(channel_id.map(snowflake))
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn guild_locale(&self, guild_id: Id<GuildMarker>) -> anyhow::Result<Option<String>> {
+    let locale: Option<Option<String>> =
+      sqlx::query_scalar("SELECT locale FROM guilds WHERE guild_id = $1")
+        .bind(snowflake(guild_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+    Ok(locale.flatten())
+  }
+
+  async fn set_guild_locale(
+    &self,
+    guild_id: Id<GuildMarker>,
+    locale: Option<&str>,
+  ) -> anyhow::Result<()> {
+    sqlx::query(
+      r#"
+        INSERT INTO guilds (guild_id, locale) VALUES ($1, $2)
+        ON CONFLICT (guild_id) DO UPDATE SET
+          locale = excluded.locale
+        ;
+      "#,
+    )
+    .bind(snowflake(guild_id))
+    .bind(locale)
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn ping(&self) -> anyhow::Result<()> {
+    sqlx::query("SELECT 1").execute(&self.pool).await?;
+
+    Ok(())
+  }
+
+  async fn close(&self) {
+    self.pool.close().await;
+  }
+}
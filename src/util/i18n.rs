@@ -1,37 +1,242 @@
-use icu_list::{ListFormatter, ListLength};
-use icu_locid::LanguageIdentifier;
+use icu_list::{ListError, ListFormatter, ListLength};
+use icu_locid::{langid, LanguageIdentifier};
+use icu_plurals::{PluralCategory, PluralRules};
 use icu_provider::DataLocale;
 use icu_provider_adapters::fallback::LocaleFallbacker;
 use icu_provider_blob::BlobDataProvider;
+use json_gettext::{get_text, JSONGetText};
+use std::{
+  fmt::Display,
+  time::{SystemTime, UNIX_EPOCH},
+};
+use twilight_model::application::interaction::Interaction;
 use writeable::Writeable;
 
+use crate::util::store::SelfRoleStore;
+
 const LIST_BLOB: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/i18n/data.postcard"));
 
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+// `LOCALES`, the locales with a translation file, which a guild can pick as its language, and
+// `TRANSLATIONS`, the contents of those files. Generated by build.rs from `i18n/`.
+include!(concat!(env!("OUT_DIR"), "/locales.rs"));
+
 thread_local! {
   static BLOB_PROVIDER: BlobDataProvider = BlobDataProvider::try_new_from_static_blob(LIST_BLOB).expect("should work");
   static FALLBACKER: LocaleFallbacker = BLOB_PROVIDER.with(|f| LocaleFallbacker::try_new_with_buffer_provider(f).unwrap());
+  static TEXTS: JSONGetText<'static> = TRANSLATIONS
+    .iter()
+    .fold(JSONGetText::build(DEFAULT_LOCALE), |mut builder, (locale, json)| {
+      builder.add_json(*locale, *json).expect("translation files should be valid");
+      builder
+    })
+    .build()
+    .expect("translation files should be valid");
 }
 
-pub fn format_list_and<W, I>(locale: LanguageIdentifier, values: I) -> String
-where
-  W: Writeable,
-  I: Iterator<Item = W> + Clone,
-{
-  let formatter = FALLBACKER.with(|f| {
+/// Locales to render an interaction's output in: the member's own for replies only they can see,
+/// and the server's for messages visible to everyone.
+#[derive(Debug, Clone)]
+pub struct Locales {
+  pub user: String,
+  pub guild: String,
+}
+
+impl Locales {
+  /// The guild locale is the language picked with `/config language`, or Discord's guild locale
+  /// if there isn't one.
+  pub async fn for_interaction(store: &dyn SelfRoleStore, interaction: &Interaction) -> Self {
+    let guild_override = match interaction.guild_id {
+      Some(guild_id) => store.guild_locale(guild_id).await.unwrap_or_else(|error| {
+        tracing::warn!("Failed to fetch language of guild {}: {}", guild_id, error);
+        None
+      }),
+      None => None,
+    };
+
+    let user = interaction
+      .locale
+      .clone()
+      .unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+    let guild = guild_override
+      .or_else(|| interaction.guild_locale.clone())
+      .unwrap_or_else(|| user.clone());
+
+    Self { user, guild }
+  }
+}
+
+fn language_identifier(locale: &str) -> LanguageIdentifier {
+  locale.parse().unwrap_or(langid!("en-US"))
+}
+
+/// Locales to look up a translation for `locale` in, from the most specific one to the least.
+pub fn fallback_chain(locale: &str) -> Vec<String> {
+  let mut chain = Vec::new();
+
+  FALLBACKER.with(|f| {
+    let key_fallbacker = f.for_config(Default::default());
+    let mut fallback_iterator =
+      key_fallbacker.fallback_for(DataLocale::from(language_identifier(locale)));
+
+    while !fallback_iterator.get().is_empty() {
+      chain.push(fallback_iterator.get().to_string());
+      fallback_iterator.step();
+    }
+  });
+
+  chain
+}
+
+fn lookup(locale: &str, key: &str) -> Option<String> {
+  TEXTS.with(|ctx| {
+    fallback_chain(locale)
+      .iter()
+      .map(String::as_str)
+      .chain([DEFAULT_LOCALE])
+      .find_map(|locale| get_text!(ctx, locale, key).map(|text| text.to_string()))
+  })
+}
+
+pub fn localize(locale: &str, key: &str) -> String {
+  lookup(locale, key).unwrap_or_else(|| {
+    tracing::warn!("Missing translation for {}", key);
+    key.to_string()
+  })
+}
+
+/// Localize `key`, replacing every `{name}` placeholder with its value from `args`.
+pub fn localize_with(locale: &str, key: &str, args: &[(&str, &dyn Display)]) -> String {
+  replace_args(localize(locale, key), args)
+}
+
+/// Localize the variant of `key` matching the plural category of `count` in `locale`, like
+/// `key#one` or `key#few`, falling back to `key#other`. `{count}` is replaced along with `args`.
+pub fn localize_plural(
+  locale: &str,
+  key: &str,
+  count: usize,
+  args: &[(&str, &dyn Display)],
+) -> String {
+  let category = with_fallback(locale, |provider, locale| {
+    PluralRules::try_new_cardinal_with_buffer_provider(provider, locale)
+  })
+  .map(|rules| rules.category_for(count))
+  .unwrap_or_else(|error| {
+    tracing::warn!("No plural rules for {}: {}", locale, error);
+    PluralCategory::Other
+  });
+
+  let text = lookup(locale, &format!("{}#{}", key, plural_suffix(category)))
+    .unwrap_or_else(|| localize(locale, &format!("{}#other", key)));
+
+  replace_args(text, args).replace("{count}", &count.to_string())
+}
+
+fn replace_args(text: String, args: &[(&str, &dyn Display)]) -> String {
+  args.iter().fold(text, |text, (name, value)| {
+    text.replace(&format!("{{{}}}", name), &value.to_string())
+  })
+}
+
+fn plural_suffix(category: PluralCategory) -> &'static str {
+  match category {
+    PluralCategory::Zero => "zero",
+    PluralCategory::One => "one",
+    PluralCategory::Two => "two",
+    PluralCategory::Few => "few",
+    PluralCategory::Many => "many",
+    PluralCategory::Other => "other",
+  }
+}
+
+/// Load ICU data for the closest locale in the fallback chain of `locale` that has it. Fails with
+/// the error for the root locale when none of them do, like for data missing from the blob.
+fn with_fallback<T, E>(
+  locale: &str,
+  load: impl Fn(&BlobDataProvider, &DataLocale) -> Result<T, E>,
+) -> Result<T, E> {
+  FALLBACKER.with(|f| {
     let key_fallbacker = f.for_config(Default::default());
-    let mut fallback_iterator = key_fallbacker.fallback_for(DataLocale::from(locale));
+    let mut fallback_iterator =
+      key_fallbacker.fallback_for(DataLocale::from(language_identifier(locale)));
 
     BLOB_PROVIDER.with(|f| loop {
       let curr_step = fallback_iterator.get();
-      let res =
-        ListFormatter::try_new_and_with_length_with_buffer_provider(f, curr_step, ListLength::Wide);
-      match res {
-        Ok(out) => return out,
-        _ => tracing::debug!("No fallback found for {}", curr_step),
+      match load(f, curr_step) {
+        Ok(out) => return Ok(out),
+        Err(error) if curr_step.is_empty() => return Err(error),
+        Err(_) => tracing::debug!("No data for {}, falling back", curr_step),
       }
       fallback_iterator.step();
     })
-  });
+  })
+}
+
+/// Joins `values` with a list formatter, or with plain commas if there's no data for one.
+fn format_list<W, I>(
+  locale: &str,
+  values: I,
+  load: impl Fn(&BlobDataProvider, &DataLocale) -> Result<ListFormatter, ListError>,
+) -> String
+where
+  W: Writeable,
+  I: Iterator<Item = W> + Clone,
+{
+  match with_fallback(locale, load) {
+    Ok(formatter) => formatter.format_to_string(values),
+    Err(error) => {
+      tracing::warn!("No list patterns for {}: {}", locale, error);
+      values
+        .map(|value| value.write_to_string().into_owned())
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+  }
+}
+
+/// "a, b and c"
+pub fn format_list_and<W, I>(locale: &str, values: I) -> String
+where
+  W: Writeable,
+  I: Iterator<Item = W> + Clone,
+{
+  format_list(locale, values, |provider, locale| {
+    ListFormatter::try_new_and_with_length_with_buffer_provider(provider, locale, ListLength::Wide)
+  })
+}
+
+/// "a, b or c"
+pub fn format_list_or<W, I>(locale: &str, values: I) -> String
+where
+  W: Writeable,
+  I: Iterator<Item = W> + Clone,
+{
+  format_list(locale, values, |provider, locale| {
+    ListFormatter::try_new_or_with_length_with_buffer_provider(provider, locale, ListLength::Wide)
+  })
+}
+
+/// How Discord renders a timestamp. Discord formats it for each viewer, in their own language and
+/// time zone, so unlike the other helpers these don't take a locale.
+#[derive(Debug, Clone, Copy)]
+pub enum TimestampStyle {
+  /// 16:20:30
+  LongTime,
+  /// in 2 months, 5 minutes ago
+  Relative,
+}
+
+pub fn format_timestamp(time: SystemTime, style: TimestampStyle) -> String {
+  let seconds = time
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or_default();
+  let style = match style {
+    TimestampStyle::LongTime => "T",
+    TimestampStyle::Relative => "R",
+  };
 
-  formatter.format_to_string(values)
+  format!("<t:{}:{}>", seconds, style)
 }
@@ -0,0 +1,149 @@
+use std::{
+  collections::HashMap,
+  sync::Arc,
+  time::{Duration, SystemTime},
+};
+use tokio::sync::{mpsc, oneshot};
+use twilight_http::Client;
+use twilight_model::id::{marker::GuildMarker, Id};
+
+use crate::util::{
+  i18n::{format_timestamp, TimestampStyle},
+  store::SelfRoleStore,
+};
+
+/// How long log lines are collected before being posted, so a busy server ends up with
+/// one message per interval instead of one per change.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+const MESSAGE_LIMIT: usize = 2000;
+
+#[derive(Debug)]
+enum Message {
+  Line(Id<GuildMarker>, String),
+  /// Flush what's pending and stop, then acknowledge.
+  Close(oneshot::Sender<()>),
+}
+
+#[derive(Debug, Clone)]
+pub struct LogChannel {
+  sender: mpsc::UnboundedSender<Message>,
+}
+
+impl LogChannel {
+  pub fn spawn(store: Arc<dyn SelfRoleStore>, client: Arc<Client>) -> Self {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(run(store, client, receiver));
+
+    Self { sender }
+  }
+
+  /// Queue a line for the guild's log channel. Guilds without one configured are skipped when
+  /// the batch is flushed. Lines are stamped with the time they were queued at, since they're
+  /// posted a while later.
+  pub fn post(&self, guild_id: Id<GuildMarker>, line: String) {
+    let line = format!(
+      "{} {}",
+      format_timestamp(SystemTime::now(), TimestampStyle::LongTime),
+      line
+    );
+    if self.sender.send(Message::Line(guild_id, line)).is_err() {
+      tracing::warn!(
+        "Log channel task is gone, dropping log line for guild {}",
+        guild_id
+      );
+    }
+  }
+
+  /// Post the lines still waiting for the next interval and stop. The store has to stay open until
+  /// this returns, since it's where the log channels are looked up.
+  pub async fn close(&self) {
+    let (ack, done) = oneshot::channel();
+    if self.sender.send(Message::Close(ack)).is_ok() {
+      let _ = done.await;
+    }
+  }
+}
+
+async fn run(
+  store: Arc<dyn SelfRoleStore>,
+  client: Arc<Client>,
+  mut receiver: mpsc::UnboundedReceiver<Message>,
+) {
+  let mut pending: HashMap<Id<GuildMarker>, Vec<String>> = HashMap::new();
+  let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+  loop {
+    tokio::select! {
+      message = receiver.recv() => match message {
+        Some(Message::Line(guild_id, line)) => pending.entry(guild_id).or_default().push(line),
+        Some(Message::Close(ack)) => {
+          flush(store.as_ref(), &client, &mut pending).await;
+          let _ = ack.send(());
+          return;
+        }
+        None => break,
+      },
+      _ = interval.tick() => flush(store.as_ref(), &client, &mut pending).await,
+    }
+  }
+
+  flush(store.as_ref(), &client, &mut pending).await;
+}
+
+async fn flush(
+  store: &dyn SelfRoleStore,
+  client: &Client,
+  pending: &mut HashMap<Id<GuildMarker>, Vec<String>>,
+) {
+  for (guild_id, lines) in pending.drain() {
+    if let Err(error) = send(store, client, guild_id, lines).await {
+      tracing::warn!(
+        "Failed to post to log channel of guild {}: {}",
+        guild_id,
+        error
+      );
+    }
+  }
+}
+
+async fn send(
+  store: &dyn SelfRoleStore,
+  client: &Client,
+  guild_id: Id<GuildMarker>,
+  lines: Vec<String>,
+) -> anyhow::Result<()> {
+  let channel_id = match store.log_channel(guild_id).await? {
+    Some(channel_id) => channel_id,
+    None => return Ok(()),
+  };
+
+  for content in chunk_lines(lines) {
+    client
+      .create_message(channel_id)
+      .content(&content)?
+      .exec()
+      .await?;
+  }
+
+  Ok(())
+}
+
+/// Join lines into as few messages as possible without going over Discord's message length limit.
+fn chunk_lines(lines: Vec<String>) -> Vec<String> {
+  let mut chunks = vec![String::new()];
+
+  for line in lines {
+    let current = chunks.last_mut().unwrap();
+    if !current.is_empty() && current.chars().count() + line.chars().count() + 1 > MESSAGE_LIMIT {
+      chunks.push(line);
+    } else {
+      if !current.is_empty() {
+        current.push('\n');
+      }
+      current.push_str(&line);
+    }
+  }
+
+  chunks.retain(|chunk| !chunk.is_empty());
+  chunks
+}
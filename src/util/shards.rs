@@ -0,0 +1,67 @@
+use std::{collections::HashMap, env, fs};
+use twilight_gateway::{cluster::ShardScheme, shard::ResumeSession};
+
+/// Where sessions are kept between restarts, unless `SESSIONS_FILE` says otherwise.
+const DEFAULT_SESSIONS_FILE: &str = "sessions.json";
+
+/// Shards this process runs: `SHARD_FROM` to `SHARD_TO` out of `SHARD_TOTAL` when a large bot is
+/// split over several processes, or `None` to run all of them as recommended by Discord.
+pub fn scheme() -> anyhow::Result<Option<ShardScheme>> {
+  let total = match env::var("SHARD_TOTAL") {
+    Ok(total) => total.parse::<u64>()?,
+    Err(_) => return Ok(None),
+  };
+  let from = match env::var("SHARD_FROM") {
+    Ok(from) => from.parse()?,
+    Err(_) => 0,
+  };
+  let to = match env::var("SHARD_TO") {
+    Ok(to) => to.parse()?,
+    Err(_) => total.saturating_sub(1),
+  };
+
+  anyhow::ensure!(
+    from <= to && to < total,
+    "Invalid shard range {from}..={to} out of {total} shards"
+  );
+
+  Ok(Some(ShardScheme::Range { from, to, total }))
+}
+
+fn sessions_file() -> String {
+  env::var("SESSIONS_FILE").unwrap_or_else(|_| DEFAULT_SESSIONS_FILE.to_string())
+}
+
+/// Sessions saved by the previous run, so shards can resume them instead of identifying again. The
+/// file is removed once read, since a session can only be resumed once.
+pub fn load_sessions() -> HashMap<u64, ResumeSession> {
+  let path = sessions_file();
+  let sessions = match fs::read(&path) {
+    Ok(sessions) => sessions,
+    Err(_) => return HashMap::new(),
+  };
+
+  if let Err(error) = fs::remove_file(&path) {
+    tracing::warn!("Failed to remove {}: {}", path, error);
+  }
+
+  match serde_json::from_slice(&sessions) {
+    Ok(sessions) => sessions,
+    Err(error) => {
+      tracing::warn!("Ignoring invalid sessions in {}: {}", path, error);
+      HashMap::new()
+    }
+  }
+}
+
+pub fn save_sessions(sessions: &HashMap<u64, ResumeSession>) {
+  let path = sessions_file();
+  let result = serde_json::to_vec(sessions)
+    .map_err(anyhow::Error::from)
+    .and_then(|sessions| Ok(fs::write(&path, sessions)?));
+
+  match result {
+    Ok(()) => tracing::info!("Saved {} sessions to {}", sessions.len(), path),
+    Err(error) => tracing::warn!("Failed to save sessions to {}: {}", path, error),
+  }
+}
@@ -0,0 +1,113 @@
+use std::{
+  future::Future,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+use tokio::sync::Notify;
+
+/// How long interactions that are still being handled get to finish once the bot is asked to stop.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Resolves once the process is asked to stop, with Ctrl+C or SIGTERM.
+pub async fn signal() {
+  #[cfg(unix)]
+  let terminate = async {
+    tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+      .expect("Failed to listen for SIGTERM")
+      .recv()
+      .await;
+  };
+  #[cfg(not(unix))]
+  let terminate = std::future::pending::<()>();
+
+  tokio::select! {
+    _ = tokio::signal::ctrl_c() => (),
+    _ = terminate => (),
+  }
+
+  tracing::info!("Shutting down");
+}
+
+/// Handlers spawned for incoming events, so shutdown can wait for the ones still running instead of
+/// cutting role updates off halfway.
+#[derive(Debug, Clone, Default)]
+pub struct Tasks {
+  inner: Arc<TasksInner>,
+}
+
+#[derive(Debug, Default)]
+struct TasksInner {
+  running: AtomicUsize,
+  idle: Notify,
+}
+
+/// Counts a task as finished when dropped, so panicking tasks don't hold up shutdown.
+struct Running(Arc<TasksInner>);
+
+impl Drop for Running {
+  fn drop(&mut self) {
+    if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+      self.0.idle.notify_waiters();
+    }
+  }
+}
+
+impl Tasks {
+  pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+    self.inner.running.fetch_add(1, Ordering::SeqCst);
+    let running = Running(Arc::clone(&self.inner));
+
+    tokio::spawn(async move {
+      let _running = running;
+      task.await;
+    });
+  }
+
+  /// Waits for the spawned tasks to finish, giving up on them after `TIMEOUT`.
+  pub async fn drain(&self) {
+    let finished = tokio::time::timeout(TIMEOUT, async {
+      loop {
+        // Registered before checking, so a task finishing in between still wakes us up.
+        let idle = self.inner.idle.notified();
+        if self.inner.running.load(Ordering::SeqCst) == 0 {
+          break;
+        }
+        idle.await;
+      }
+    })
+    .await;
+
+    if finished.is_err() {
+      tracing::warn!(
+        "{} tasks still running after {:?}, stopping anyway",
+        self.inner.running.load(Ordering::SeqCst),
+        TIMEOUT
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::atomic::AtomicBool;
+
+  #[tokio::test]
+  async fn drain_waits_for_running_tasks() {
+    let tasks = Tasks::default();
+    let done = Arc::new(AtomicBool::new(false));
+
+    let task_done = Arc::clone(&done);
+    tasks.spawn(async move {
+      tokio::time::sleep(Duration::from_millis(50)).await;
+      task_done.store(true, Ordering::SeqCst);
+    });
+    tasks.drain().await;
+
+    assert!(done.load(Ordering::SeqCst));
+    assert_eq!(tasks.inner.running.load(Ordering::SeqCst), 0);
+  }
+}
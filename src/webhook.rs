@@ -0,0 +1,297 @@
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use hyper::{
+  body::HttpBody,
+  header::{CONTENT_LENGTH, CONTENT_TYPE},
+  service::{make_service_fn, service_fn},
+  Body, Method, Request, Response, Server, StatusCode,
+};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use tokio::sync::oneshot;
+use twilight_model::{
+  application::interaction::{Interaction, InteractionType},
+  gateway::payload::incoming::InteractionCreate,
+  http::interaction::{InteractionResponse, InteractionResponseType},
+};
+
+use crate::{
+  events::{self, Responder},
+  health,
+  util::shutdown,
+  State,
+};
+
+const SIGNATURE: &str = "X-Signature-Ed25519";
+const TIMESTAMP: &str = "X-Signature-Timestamp";
+/// Interactions are a few KiB at most, anything much larger isn't from Discord.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// Parses the application's public key, as shown hex encoded in the developer portal.
+pub fn public_key(hex_key: &str) -> anyhow::Result<PublicKey> {
+  Ok(PublicKey::from_bytes(&hex::decode(hex_key)?)?)
+}
+
+/// Serves Discord's interactions webhook on `addr`, as an alternative to receiving interactions
+/// over the gateway.
+pub async fn serve(state: State, addr: SocketAddr, public_key: PublicKey) -> anyhow::Result<()> {
+  let tasks = state.tasks.clone();
+
+  // Discord only sends requests when someone uses the bot, so there's nothing to beat on when it's
+  // idle. Liveness only tells whether the runtime still gets around to running tasks here, a stuck
+  // handler or listener isn't detected.
+  let health = Arc::clone(&state.health);
+  tokio::spawn(async move {
+    let mut heartbeat = tokio::time::interval(health::HEARTBEAT_INTERVAL);
+    loop {
+      heartbeat.tick().await;
+      health.beat();
+    }
+  });
+
+  let make_service = make_service_fn(move |_| {
+    let state = state.clone();
+    async move {
+      Ok::<_, Infallible>(service_fn(move |request| {
+        handle(state.clone(), public_key, request)
+      }))
+    }
+  });
+
+  tracing::info!("Listening for interactions on {addr}");
+  Server::bind(&addr)
+    .serve(make_service)
+    .with_graceful_shutdown(shutdown::signal())
+    .await?;
+
+  // Deferred handlers keep going after their request got its response.
+  tasks.drain().await;
+
+  Ok(())
+}
+
+async fn handle(
+  state: State,
+  public_key: PublicKey,
+  request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+  Ok(
+    respond(state, public_key, request)
+      .await
+      .unwrap_or_else(|status| {
+        Response::builder()
+          .status(status)
+          .body(Body::empty())
+          .unwrap()
+      }),
+  )
+}
+
+async fn respond(
+  state: State,
+  public_key: PublicKey,
+  request: Request<Body>,
+) -> Result<Response<Body>, StatusCode> {
+  if request.method() != Method::POST {
+    return Err(StatusCode::METHOD_NOT_ALLOWED);
+  }
+
+  let header = |name: &str| {
+    request
+      .headers()
+      .get(name)
+      .and_then(|value| value.to_str().ok())
+      .map(str::to_string)
+      .ok_or(StatusCode::UNAUTHORIZED)
+  };
+  let signature = header(SIGNATURE)?;
+  let timestamp = header(TIMESTAMP)?;
+
+  // The body has to be read before the signature can be checked, so its size is checked first.
+  let length = request
+    .headers()
+    .get(CONTENT_LENGTH)
+    .and_then(|value| value.to_str().ok())
+    .and_then(|value| value.parse::<usize>().ok())
+    .ok_or(StatusCode::LENGTH_REQUIRED)?;
+  if length > MAX_BODY_SIZE {
+    return Err(StatusCode::PAYLOAD_TOO_LARGE);
+  }
+  let body = read_body(request.into_body(), length).await?;
+
+  // Discord checks that invalid signatures are rejected before it accepts the endpoint.
+  if !verify(&public_key, &signature, &timestamp, &body) {
+    return Err(StatusCode::UNAUTHORIZED);
+  }
+
+  let interaction: Interaction = serde_json::from_slice(&body).map_err(|error| {
+    tracing::warn!("Failed to parse interaction: {}", error);
+    StatusCode::BAD_REQUEST
+  })?;
+
+  let response = if interaction.kind == InteractionType::Ping {
+    InteractionResponse {
+      kind: InteractionResponseType::Pong,
+      data: None,
+    }
+  } else {
+    let (sender, receiver) = oneshot::channel();
+    let tasks = state.tasks.clone();
+    tasks.spawn(async move {
+      let interaction = Box::new(InteractionCreate(interaction));
+      if let Err(error) =
+        events::interaction_dispatcher(state, interaction, Responder::Http(sender)).await
+      {
+        tracing::error!("{}", error);
+      }
+    });
+
+    receiver
+      .await
+      .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+  };
+
+  let body = serde_json::to_vec(&response).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+  Ok(
+    Response::builder()
+      .header(CONTENT_TYPE, "application/json")
+      .body(body.into())
+      .unwrap(),
+  )
+}
+
+/// Reads at most `limit` bytes of `body`, failing instead of buffering anything past that.
+async fn read_body(mut body: Body, limit: usize) -> Result<Vec<u8>, StatusCode> {
+  let mut bytes = Vec::with_capacity(limit);
+  while let Some(chunk) = body.data().await {
+    let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+    if bytes.len() + chunk.len() > limit {
+      return Err(StatusCode::PAYLOAD_TOO_LARGE);
+    }
+    bytes.extend_from_slice(&chunk);
+  }
+
+  Ok(bytes)
+}
+
+/// Discord signs the timestamp followed by the body with the application's key.
+fn verify(public_key: &PublicKey, signature: &str, timestamp: &str, body: &[u8]) -> bool {
+  let signature = match hex::decode(signature)
+    .ok()
+    .and_then(|bytes| Signature::try_from(bytes.as_slice()).ok())
+  {
+    Some(signature) => signature,
+    None => return false,
+  };
+
+  let message = [timestamp.as_bytes(), body].concat();
+  public_key.verify(&message, &signature).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::util::store::InMemoryStore;
+  use ed25519_dalek::{Keypair, SecretKey, Signer};
+  use hyper::body;
+
+  const PING: &[u8] = include_bytes!("../fixtures/interactions/ping.json");
+  const PERSIST: &[u8] = include_bytes!("../fixtures/interactions/persist.json");
+
+  fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = PublicKey::from(&secret);
+    Keypair { secret, public }
+  }
+
+  fn signed(keypair: &Keypair, body: &[u8]) -> Request<Body> {
+    let timestamp = "1666000000";
+    let signature = keypair.sign(&[timestamp.as_bytes(), body].concat());
+
+    Request::post("/")
+      .header(SIGNATURE, hex::encode(signature.to_bytes()))
+      .header(TIMESTAMP, timestamp)
+      .header(CONTENT_LENGTH, body.len())
+      .body(Body::from(body.to_vec()))
+      .unwrap()
+  }
+
+  async fn post(request: Request<Body>) -> (StatusCode, Option<serde_json::Value>) {
+    let state = State::with_store(Arc::new(InMemoryStore::default()));
+    let response = handle(state, keypair(1).public, request).await.unwrap();
+    let status = response.status();
+    let body = body::to_bytes(response.into_body()).await.unwrap();
+
+    (status, serde_json::from_slice(&body).ok())
+  }
+
+  #[tokio::test]
+  async fn answers_ping_with_pong() {
+    let (status, body) = post(signed(&keypair(1), PING)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.unwrap()["type"], 1);
+  }
+
+  #[tokio::test]
+  async fn rejects_invalid_signatures() {
+    let (status, _) = post(signed(&keypair(2), PING)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let mut request = signed(&keypair(1), PING);
+    *request.body_mut() = Body::from(PERSIST);
+    request
+      .headers_mut()
+      .insert(CONTENT_LENGTH, PERSIST.len().into());
+    let (status, _) = post(request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let request = Request::post("/").body(Body::from(PING)).unwrap();
+    let (status, _) = post(request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+  }
+
+  #[tokio::test]
+  async fn rejects_bodies_without_or_over_the_size_limit() {
+    let mut request = signed(&keypair(1), PING);
+    request.headers_mut().remove(CONTENT_LENGTH);
+    let (status, _) = post(request).await;
+    assert_eq!(status, StatusCode::LENGTH_REQUIRED);
+
+    let (status, _) = post(signed(&keypair(1), &[b' '; MAX_BODY_SIZE + 1])).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    // A body longer than it claims to be isn't read past its length either.
+    let mut request = signed(&keypair(1), PING);
+    request.headers_mut().insert(CONTENT_LENGTH, 2.into());
+    let (status, _) = post(request).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+  }
+
+  #[tokio::test]
+  async fn answers_unsupported_interactions_with_an_error() {
+    let mut autocomplete: serde_json::Value = serde_json::from_slice(PERSIST).unwrap();
+    autocomplete["type"] = 4.into();
+    let mut modal = autocomplete.clone();
+    modal["type"] = 5.into();
+    modal["data"] = serde_json::json!({ "custom_id": "modal", "components": [] });
+
+    for interaction in [autocomplete, modal] {
+      let body = serde_json::to_vec(&interaction).unwrap();
+      let (status, body) = post(signed(&keypair(1), &body)).await;
+      assert_eq!(status, StatusCode::OK);
+      assert_eq!(
+        body.unwrap()["data"]["content"],
+        "This interaction isn't supported."
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn responds_to_commands_in_the_body() {
+    let (status, body) = post(signed(&keypair(1), PERSIST)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let body = body.unwrap();
+    assert_eq!(body["type"], 4);
+    assert_eq!(body["data"]["content"], "Pick your roles");
+  }
+}